@@ -76,3 +76,11 @@ extern "C" {
         base_display_addr: usize,
     );
 }
+extern "C" {
+    pub fn Editor_CalcSizes(
+        editor: *mut MemoryEditor,
+        s: *mut MemoryEditor_Sizes,
+        mem_size: usize,
+        base_display_addr: usize,
+    );
+}
@@ -9,4 +9,7 @@ extern "C" {
     void Editor_DrawWindow(MemoryEditor& editor, const char* title, void* mem_data, size_t mem_size, size_t base_display_addr) {
         editor.DrawWindow(title, mem_data, mem_size, base_display_addr);
     }
+    void Editor_CalcSizes(MemoryEditor& editor, MemoryEditor::Sizes& s, size_t mem_size, size_t base_display_addr) {
+        editor.CalcSizes(s, mem_size, base_display_addr);
+    }
 }
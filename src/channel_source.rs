@@ -0,0 +1,180 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::sync::mpsc::{Receiver, Sender, TryRecvError};
+
+// Sent to the worker thread
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ChannelRequest {
+    // Reply with (offset, value, generation) on the response channel, passing the generation back as is
+    Read(usize, u64),
+    Write(usize, u8),
+}
+
+// Memory read and written by a worker thread, for targets too slow to read from while drawing.
+// Draws are served from a local cache. Bytes missing from it are requested from the worker and shown as ??
+// until the worker answers, so the editor is eventually consistent with the target: writes show up immediately,
+// but changes made to the target by anything else only show up after invalidate.
+pub struct ChannelSource {
+    requests: Sender<ChannelRequest>,
+    responses: Receiver<(usize, u8, u64)>,
+    cache: RefCell<HashMap<usize, u8>>,
+    // Requested but not answered yet
+    pending: RefCell<HashSet<usize>>,
+    // Sent along with reads and bumped by invalidate, to tell answers to reads from before it apart
+    generation: u64,
+}
+
+impl ChannelSource {
+    pub fn new(requests: Sender<ChannelRequest>, responses: Receiver<(usize, u8, u64)>) -> ChannelSource {
+        ChannelSource {
+            requests,
+            responses,
+            cache: RefCell::new(HashMap::new()),
+            pending: RefCell::new(HashSet::new()),
+            generation: 0,
+        }
+    }
+
+    // Cached byte, requesting it if missing
+    pub fn read(&self, offset: usize) -> Option<u8> {
+        self.poll();
+        if let Some(&d) = self.cache.borrow().get(&offset) {
+            return Some(d);
+        }
+        // A worker that hung up just leaves the byte missing
+        if self.pending.borrow_mut().insert(offset) {
+            let _ = self.requests.send(ChannelRequest::Read(offset, self.generation));
+        }
+        None
+    }
+
+    // Updates the cache right away and sends the write to the worker.
+    // A read of the offset still in flight was answered with the value from before the write, so it's dropped.
+    pub fn write(&mut self, offset: usize, value: u8) {
+        self.pending.get_mut().remove(&offset);
+        self.cache.get_mut().insert(offset, value);
+        let _ = self.requests.send(ChannelRequest::Write(offset, value));
+    }
+
+    // Preallocate the cache for at least this many more bytes
+    pub fn reserve(&mut self, additional: usize) {
+        self.cache.get_mut().reserve(additional);
+        self.pending.get_mut().reserve(additional);
+    }
+
+    #[inline]
+    pub fn is_cached(&self, offset: usize) -> bool {
+        self.cache.borrow().contains_key(&offset)
+    }
+
+    // Move answers from the worker into the cache. Called by every read.
+    // Answers to reads that aren't pending anymore because of a write since, or that were requested before the last
+    // invalidate, are stale and dropped.
+    pub fn poll(&self) {
+        let mut cache = self.cache.borrow_mut();
+        let mut pending = self.pending.borrow_mut();
+        loop {
+            match self.responses.try_recv() {
+                Ok((offset, value, generation)) => {
+                    if generation == self.generation && pending.remove(&offset) {
+                        cache.insert(offset, value);
+                    }
+                }
+                Err(TryRecvError::Empty) | Err(TryRecvError::Disconnected) => break,
+            }
+        }
+    }
+
+    // Forget everything cached, so every byte is requested again when drawn.
+    // Reads still in flight are requested again too, since their answers may be from before the target changed.
+    pub fn invalidate(&mut self) {
+        self.cache.get_mut().clear();
+        self.pending.get_mut().clear();
+        self.generation = self.generation.wrapping_add(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc::channel;
+
+    // The worker side of the channels, answered by hand
+    fn source() -> (ChannelSource, Receiver<ChannelRequest>, Sender<(usize, u8, u64)>) {
+        let (requests, worker_requests) = channel();
+        let (worker_responses, responses) = channel();
+        (ChannelSource::new(requests, responses), worker_requests, worker_responses)
+    }
+
+    #[test]
+    fn reads_are_requested_once_and_cached() {
+        let (source, requests, responses) = source();
+        assert_eq!(source.read(3), None);
+        assert_eq!(source.read(3), None);
+        assert_eq!(requests.try_recv(), Ok(ChannelRequest::Read(3, 0)));
+        assert!(requests.try_recv().is_err());
+
+        responses.send((3, 0xAB, 0)).unwrap();
+        assert_eq!(source.read(3), Some(0xAB));
+        assert!(source.is_cached(3));
+        assert!(requests.try_recv().is_err());
+    }
+
+    #[test]
+    fn out_of_order_answers_are_cached() {
+        let (source, requests, responses) = source();
+        assert_eq!(source.read(1), None);
+        assert_eq!(source.read(2), None);
+        assert_eq!(requests.try_iter().collect::<Vec<_>>(), [ChannelRequest::Read(1, 0), ChannelRequest::Read(2, 0)]);
+
+        responses.send((2, 20, 0)).unwrap();
+        assert_eq!(source.read(1), None);
+        assert_eq!(source.read(2), Some(20));
+        responses.send((1, 10, 0)).unwrap();
+        assert_eq!(source.read(1), Some(10));
+        assert!(requests.try_recv().is_err());
+    }
+
+    #[test]
+    fn answers_to_reads_from_before_a_write_are_dropped() {
+        let (mut source, requests, responses) = source();
+        assert_eq!(source.read(5), None);
+        source.write(5, 0x55);
+        assert_eq!(requests.try_iter().collect::<Vec<_>>(), [ChannelRequest::Read(5, 0), ChannelRequest::Write(5, 0x55)]);
+
+        // The worker answers the read with the value it had before the write
+        responses.send((5, 0x00, 0)).unwrap();
+        assert_eq!(source.read(5), Some(0x55));
+    }
+
+    #[test]
+    fn invalidate_requests_again_and_drops_unrequested_answers() {
+        let (mut source, requests, responses) = source();
+        assert_eq!(source.read(0), None);
+        responses.send((0, 1, 0)).unwrap();
+        assert_eq!(source.read(0), Some(1));
+
+        source.invalidate();
+        assert!(!source.is_cached(0));
+        responses.send((7, 7, 1)).unwrap();
+        assert_eq!(source.read(0), None);
+        assert!(!source.is_cached(7));
+        assert_eq!(requests.try_iter().collect::<Vec<_>>(), [ChannelRequest::Read(0, 0), ChannelRequest::Read(0, 1)]);
+    }
+
+    #[test]
+    fn answers_to_reads_from_before_invalidate_are_dropped() {
+        let (mut source, requests, responses) = source();
+        assert_eq!(source.read(4), None);
+        source.invalidate();
+        assert_eq!(source.read(4), None);
+        assert_eq!(requests.try_iter().collect::<Vec<_>>(), [ChannelRequest::Read(4, 0), ChannelRequest::Read(4, 1)]);
+
+        // The first read is answered with the value from before the target changed
+        responses.send((4, 0x00, 0)).unwrap();
+        assert_eq!(source.read(4), None);
+        responses.send((4, 0x44, 1)).unwrap();
+        assert_eq!(source.read(4), Some(0x44));
+        assert!(requests.try_recv().is_err());
+    }
+}
@@ -0,0 +1,309 @@
+use std::ptr::NonNull;
+
+use imgui::sys::{ImDrawList, ImVec2};
+use imgui::{ImColor32, Ui, WindowFocusedFlags, WindowHoveredFlags};
+
+// What the C++ editor is reading a byte for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Read {
+    Hex,
+    Ascii,
+    // Data preview, or anything else outside of the scrolling region
+    Other,
+}
+
+// Geometry of the last drawn frame.
+// The C++ editor does all of its own layout, so this is captured from inside the highlight callback,
+// which is invoked once per visible cell with the cursor positioned at the top left of that cell.
+// Highlighted cells also invoke it for the next cell before drawing, to join highlights up, see is_lookahead.
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct Layout {
+    pub sizes: sys::MemoryEditor_Sizes,
+    pub cols: usize,
+    pub mid_cols: usize,
+    pub mem_size: usize,
+    // Screen position of the top left of the first row, adjusted for scrolling
+    pub origin: [f32; 2],
+    // Visible cells as [first, last)
+    pub visible: Option<(usize, usize)>,
+    pub window_pos: [f32; 2],
+    pub window_size: [f32; 2],
+    pub scroll_y: f32,
+    pub scroll_max_y: f32,
+    pub focused: bool,
+    pub hovered: bool,
+    // Cell under the mouse, in either the hex or ASCII column
+    pub hovered_addr: Option<usize>,
+    // Draw list of the scrolling region
+    pub draw_list: Option<NonNull<imgui::sys::ImDrawList>>,
+    // Digits of the C++ editor's own addresses, before making room for custom address labels
+    pub addr_digits: usize,
+    // Extra space between the hex and ASCII columns, which the ASCII column is drawn shifted by
+    pub ascii_shift: f32,
+    // Whether HexII was turned on for drawing cell values here, rather than by the user
+    pub hex_ii_forced: bool,
+    // Whether the frame budget ran out, after which bytes aren't read anymore
+    pub truncated: bool,
+    // Cell visited last whose hex value hasn't been read yet
+    hex_read: Option<usize>,
+    // Cell visited last and the cursor position it was visited at
+    last_visit: Option<(usize, [f32; 2])>,
+}
+
+// Draw list of the scrolling region, for drawing on top of everything the C++ editor drew once it is done.
+// Drawing is clipped to the region, whose own clip rect has been popped by then.
+pub(crate) struct RegionDrawList(NonNull<ImDrawList>);
+
+impl RegionDrawList {
+    pub fn rect(&self, min: [f32; 2], max: [f32; 2], color: ImColor32) {
+        unsafe { imgui::sys::ImDrawList_AddRectFilled(self.0.as_ptr(), vec2(min), vec2(max), color.to_bits(), 0.0, 0) }
+    }
+
+    pub fn line(&self, p1: [f32; 2], p2: [f32; 2], color: ImColor32) {
+        unsafe { imgui::sys::ImDrawList_AddLine(self.0.as_ptr(), vec2(p1), vec2(p2), color.to_bits(), 1.0) }
+    }
+
+    pub fn text(&self, pos: [f32; 2], color: ImColor32, text: &str) {
+        unsafe {
+            imgui::sys::ImDrawList_AddText_Vec2(
+                self.0.as_ptr(),
+                vec2(pos),
+                color.to_bits(),
+                text.as_ptr() as *const _,
+                text.as_ptr().add(text.len()) as *const _,
+            )
+        }
+    }
+}
+
+impl Drop for RegionDrawList {
+    fn drop(&mut self) {
+        unsafe { imgui::sys::ImDrawList_PopClipRect(self.0.as_ptr()) }
+    }
+}
+
+fn vec2([x, y]: [f32; 2]) -> ImVec2 {
+    ImVec2::new(x, y)
+}
+
+// MemoryEditor::CalcSizes, which only reads from the editor
+pub(crate) fn calc_sizes(raw: &sys::MemoryEditor, mem_size: usize, base_addr: usize) -> sys::MemoryEditor_Sizes {
+    let mut raw = *raw;
+    let mut sizes = Default::default();
+    unsafe { sys::Editor_CalcSizes(&mut raw, &mut sizes, mem_size, base_addr) }
+    sizes
+}
+
+impl Layout {
+    // Reset for a new frame. Must be called before the C++ editor draws.
+    pub fn begin(&mut self, raw: &sys::MemoryEditor, mem_size: usize, base_addr: usize, ascii_shift: f32) {
+        let mut sizes = calc_sizes(raw, mem_size, base_addr);
+        let ascii_shift = if raw.OptShowAscii { ascii_shift } else { 0.0 };
+        sizes.PosAsciiStart += ascii_shift;
+        sizes.PosAsciiEnd += ascii_shift;
+        sizes.WindowWidth += ascii_shift;
+        *self = Layout {
+            sizes,
+            ascii_shift,
+            cols: raw.Cols.max(1) as usize,
+            mid_cols: raw.OptMidColsCount.max(0) as usize,
+            mem_size,
+            ..Default::default()
+        };
+    }
+
+    // Whether the C++ editor is asking about addr while still drawing the cell before it, which it does for
+    // highlighted cells to see if the highlight continues. The cursor hasn't moved since that cell's visit.
+    // These aren't visits, and the cell is visited again once the cursor is on it.
+    pub fn is_lookahead(&self, ui: &Ui, addr: usize) -> bool {
+        match self.last_visit {
+            Some((last, pos)) => addr == last + 1 && pos == ui.cursor_screen_pos(),
+            None => false,
+        }
+    }
+
+    // Record a visited cell. Returns true for the first cell of the frame.
+    // expects_read is false when the C++ editor won't read the cell's hex value, i.e. it is being edited.
+    pub fn visit(&mut self, ui: &Ui, addr: usize, expects_read: bool) -> bool {
+        let pos = ui.cursor_screen_pos();
+        let first = self.visible.is_none();
+        if first {
+            self.origin = [
+                pos[0] - self.byte_pos_x(addr % self.cols),
+                pos[1] - self.row(addr) as f32 * self.sizes.LineHeight,
+            ];
+            self.window_pos = ui.window_pos();
+            self.window_size = ui.window_size();
+            self.scroll_y = ui.scroll_y();
+            self.scroll_max_y = ui.scroll_max_y();
+            self.focused = ui.is_window_focused_with_flags(WindowFocusedFlags::ROOT_AND_CHILD_WINDOWS);
+            self.hovered = ui.is_window_hovered_with_flags(WindowHoveredFlags::empty());
+            self.draw_list = NonNull::new(unsafe { imgui::sys::igGetWindowDrawList() });
+            if self.hovered {
+                self.hovered_addr = self.addr_at(ui.io().mouse_pos);
+            }
+        }
+        let (start, end) = self.visible.unwrap_or((addr, addr + 1));
+        self.visible = Some((start.min(addr), end.max(addr + 1)));
+        self.hex_read = if expects_read { Some(addr) } else { None };
+        self.last_visit = Some((addr, pos));
+        first
+    }
+
+    // Each visited cell is read once for its hex value right after the visit.
+    // The ASCII column of a row is read after all of its cells were visited, while still in the scrolling region.
+    pub fn classify_read(&mut self, addr: usize) -> Read {
+        if self.hex_read.take() == Some(addr) {
+            Read::Hex
+        } else if self.draw_list.is_some() && NonNull::new(unsafe { imgui::sys::igGetWindowDrawList() }) == self.draw_list {
+            Read::Ascii
+        } else {
+            Read::Other
+        }
+    }
+
+    #[inline]
+    pub fn row(&self, addr: usize) -> usize {
+        addr / self.cols
+    }
+
+    // Offset of a hex cell from the start of its row
+    pub fn byte_pos_x(&self, col: usize) -> f32 {
+        let mut x = self.sizes.PosHexStart + self.sizes.HexCellWidth * col as f32;
+        if self.mid_cols > 0 {
+            x += (col / self.mid_cols) as f32 * self.sizes.SpacingBetweenMidCols;
+        }
+        x
+    }
+
+    // Screen position of the top left of the hex cell at addr
+    pub fn cell_pos(&self, addr: usize) -> [f32; 2] {
+        [
+            self.origin[0] + self.byte_pos_x(addr % self.cols),
+            self.origin[1] + self.row(addr) as f32 * self.sizes.LineHeight,
+        ]
+    }
+
+    // Screen position of the character at addr in the ASCII column
+    pub fn ascii_pos(&self, addr: usize) -> [f32; 2] {
+        [
+            self.origin[0] + self.sizes.PosAsciiStart + (addr % self.cols) as f32 * self.sizes.GlyphWidth,
+            self.origin[1] + self.row(addr) as f32 * self.sizes.LineHeight,
+        ]
+    }
+
+    // Whether a screen position is inside the scrolling region
+    pub fn contains(&self, pos: [f32; 2]) -> bool {
+        self.visible.is_some()
+            && pos[0] >= self.window_pos[0]
+            && pos[1] >= self.window_pos[1]
+            && pos[0] < self.window_pos[0] + self.window_size[0]
+            && pos[1] < self.window_pos[1] + self.window_size[1]
+    }
+
+    // Cell at a screen position, in either the hex or ASCII column
+    pub fn addr_at(&self, pos: [f32; 2]) -> Option<usize> {
+        let x = pos[0] - self.origin[0];
+        let y = pos[1] - self.origin[1];
+        if y < 0.0 || self.sizes.LineHeight <= 0.0 {
+            return None;
+        }
+        let col = if x >= self.sizes.PosAsciiStart && x < self.sizes.PosAsciiEnd {
+            ((x - self.sizes.PosAsciiStart) / self.sizes.GlyphWidth) as usize
+        } else {
+            (0..self.cols).find(|&col| {
+                let start = self.byte_pos_x(col);
+                x >= start && x < start + self.sizes.HexCellWidth
+            })?
+        };
+        let addr = (y / self.sizes.LineHeight) as usize * self.cols + col.min(self.cols - 1);
+        if addr < self.mem_size {
+            Some(addr)
+        } else {
+            None
+        }
+    }
+
+    // Full width of a row, from the start of the hex column to the end of the ASCII column
+    pub fn row_rect(&self, addr: usize) -> ([f32; 2], [f32; 2]) {
+        let y = self.origin[1] + self.row(addr) as f32 * self.sizes.LineHeight;
+        (
+            [self.origin[0] + self.sizes.PosHexStart, y],
+            [self.origin[0] + self.sizes.PosAsciiEnd, y + self.sizes.LineHeight],
+        )
+    }
+
+    // Scroll position as a fraction of the scrollable height
+    pub fn scroll_fraction(&self) -> f32 {
+        if self.scroll_max_y > 0.0 {
+            self.scroll_y / self.scroll_max_y
+        } else {
+            0.0
+        }
+    }
+
+    // None until the C++ editor has drawn this frame
+    pub fn region_draw_list(&self) -> Option<RegionDrawList> {
+        let draw_list = self.draw_list.filter(|_| self.visible.is_some())?;
+        let max = [self.window_pos[0] + self.window_size[0], self.window_pos[1] + self.window_size[1]];
+        unsafe { imgui::sys::ImDrawList_PushClipRect(draw_list.as_ptr(), vec2(self.window_pos), vec2(max), false) }
+        Some(RegionDrawList(draw_list))
+    }
+
+    // Number of rows that fit in the scrolling region
+    pub fn visible_rows(&self) -> usize {
+        if self.sizes.LineHeight <= 0.0 {
+            return 0;
+        }
+        (self.window_size[1] / self.sizes.LineHeight) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 4 columns with a mid column gap after the second, 10 bytes, first row at (100, 50)
+    fn layout() -> Layout {
+        let mut layout = Layout { cols: 4, mid_cols: 2, mem_size: 10, origin: [100.0, 50.0], ..Default::default() };
+        layout.sizes.LineHeight = 20.0;
+        layout.sizes.GlyphWidth = 10.0;
+        layout.sizes.HexCellWidth = 25.0;
+        layout.sizes.SpacingBetweenMidCols = 5.0;
+        layout.sizes.PosHexStart = 40.0;
+        layout.sizes.PosAsciiStart = 160.0;
+        layout.sizes.PosAsciiEnd = 200.0;
+        layout
+    }
+
+    #[test]
+    fn cell_positions() {
+        let layout = layout();
+        let xs: Vec<f32> = (0..4).map(|col| layout.byte_pos_x(col)).collect();
+        assert_eq!(xs, [40.0, 65.0, 95.0, 120.0]);
+        assert_eq!(layout.cell_pos(5), [165.0, 70.0]);
+        assert_eq!(layout.ascii_pos(5), [270.0, 70.0]);
+    }
+
+    #[test]
+    fn addr_at() {
+        let layout = layout();
+        // Third cell of the second row, after the mid column gap
+        assert_eq!(layout.addr_at([196.0, 71.0]), Some(6));
+        assert_eq!(layout.addr_at([192.0, 71.0]), None);
+        // Second character of the ASCII column
+        assert_eq!(layout.addr_at([275.0, 51.0]), Some(1));
+        assert_eq!(layout.addr_at([221.0, 91.0]), None);
+        assert_eq!(layout.addr_at([141.0, 49.0]), None);
+        assert_eq!(layout.row_rect(5), ([140.0, 70.0], [300.0, 90.0]));
+    }
+
+    #[test]
+    fn visible_rows() {
+        let mut layout = layout();
+        layout.window_size = [0.0, 95.0];
+        assert_eq!(layout.visible_rows(), 4);
+        layout.sizes.LineHeight = 0.0;
+        assert_eq!(layout.visible_rows(), 0);
+    }
+}
@@ -1,4 +1,9 @@
 pub extern crate imgui_memory_editor_sys as sys;
 
+pub mod channel_source;
+mod layout;
 pub mod memory_editor;
+pub mod preview;
+pub use channel_source::{ChannelRequest, ChannelSource};
 pub use memory_editor::*;
+pub use preview::PreviewDataType;
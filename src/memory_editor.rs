@@ -1,27 +1,425 @@
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, VecDeque};
 use std::ffi::c_void;
+use std::io::{self, Write};
+use std::ops::Range;
+use std::ptr;
+use std::time::{Duration, Instant};
 
-use imgui::{ImColor32, ImStr, Ui};
+use imgui::{
+    im_str, ChildWindow, CollapsingHeader, ComboBox, ComboBoxFlags, Condition, ImColor32, ImStr, ImString, Key, MouseButton, Selectable, StyleColor, StyleVar, Ui,
+    Window, WindowFlags, WindowHoveredFlags,
+};
+
+use crate::channel_source::ChannelSource;
+use crate::layout::{self, Layout, Read};
+use crate::preview::{Preview, PreviewDataType};
 
 
 // TODO: Alias ReadHandlerTrait and writeHandlerTrait to FnMuts once trait_alias is stabilized
-type ReadHandler<'a, T> = Option<Box<dyn FnMut(&T, usize) -> u8 + 'a>>;
-type WriteHandler<'a, T> = Option<Box<dyn FnMut(&mut T, usize, u8) + 'a>>;
-type HighlightHandler<'a, T> = Option<Box<dyn FnMut(&T, usize) -> bool + 'a>>;
-type MemData<'a, 'b, T> = (
-    &'b mut ReadHandler<'a, T>,
-    &'b mut WriteHandler<'a, T>,
-    &'b mut HighlightHandler<'a, T>,
-    &'b mut T
-);
-
-pub struct MemoryEditor<'a, T> {
+type ReadHandler<'a, T, C> = Option<Box<dyn FnMut(&T, &mut C, usize) -> u8 + 'a>>;
+type WriteHandler<'a, T, C> = Option<Box<dyn FnMut(&mut T, &mut C, usize, u8) + 'a>>;
+type WriteResultHandler<'a, T, C> = Option<Box<dyn FnMut(&mut T, &mut C, usize, u8) -> Result<(), String> + 'a>>;
+type HighlightHandler<'a, T, C> = Option<Box<dyn FnMut(&T, &mut C, usize) -> bool + 'a>>;
+type DisplayHandler<'a, T, C> = Option<Box<dyn FnMut(&T, &mut C, usize, u8) -> u8 + 'a>>;
+type FlagHandler<'a, T, C> = Option<Box<dyn FnMut(&T, &mut C, usize) -> Option<ImColor32> + 'a>>;
+type DiffHandler<'a> = Option<Box<dyn FnMut(usize, u8, u8) -> bool + 'a>>;
+type KeyHandler<'a, T, C> = Option<Box<dyn FnMut(&mut T, &mut C, Key, &KeyMods, Option<Range<usize>>) + 'a>>;
+type CopyFormatHandler<'a> = Option<Box<dyn FnMut(&[u8]) -> String + 'a>>;
+type WriteErrorHandler<'a> = Option<Box<dyn FnMut(usize, &str) + 'a>>;
+type OpenHandler<'a> = Option<Box<dyn FnMut() + 'a>>;
+type SelectionHandler<'a> = Option<Box<dyn FnMut(Range<usize>) + 'a>>;
+type DeleteHandler<'a, T, C> = Option<Box<dyn FnMut(&mut T, &mut C, Range<usize>) + 'a>>;
+type AddrMapHandler<'a> = Option<Box<dyn FnMut(usize) -> usize + 'a>>;
+
+// Number of addresses kept by goto_addr
+const HISTORY_LEN: usize = 32;
+// Width of a row sparkline, in glyphs
+const SPARKLINE_GLYPHS: f32 = 8.0;
+// Width of the overview bar, in glyphs
+const OVERVIEW_GLYPHS: f32 = 1.5;
+
+// Passed to the C++ editor as mem_data and handed back to the wrappers below.
+// Handlers are taken off the editor for the duration of a draw so the editor itself can be reached through `editor`.
+struct MemData<'a, 'b, T, C> {
+    editor: *mut MemoryEditor<'a, T, C>,
+    // The editor's context, reached the same way as the editor
+    ctx: *mut C,
+    ui: &'b Ui<'b>,
+    read_fn: Option<&'b mut dyn FnMut(&T, &mut C, usize) -> u8>,
+    write_fn: Option<&'b mut dyn FnMut(&mut T, &mut C, usize, u8)>,
+    write_result_fn: Option<&'b mut dyn FnMut(&mut T, &mut C, usize, u8) -> Result<(), String>>,
+    highlight_fn: Option<&'b mut dyn FnMut(&T, &mut C, usize) -> bool>,
+    display_fn: Option<&'b mut dyn FnMut(&T, &mut C, usize, u8) -> u8>,
+    flag_fn: Option<&'b mut dyn FnMut(&T, &mut C, usize) -> Option<ImColor32>>,
+    source: Source<'b, T>,
+    // Bytes read so far, by the C++ editor and by everything drawn here
+    reads: usize,
+}
+
+enum Source<'b, T> {
+    User(&'b mut T),
+    Bytes(*mut u8),
+}
+
+impl<'a, 'b, T, C> MemData<'a, 'b, T, C> {
+    fn read(&mut self, off: usize) -> u8 {
+        self.reads += 1;
+        let ctx = unsafe { &mut *self.ctx };
+        match &mut self.source {
+            Source::User(user_data) => self.read_fn.as_mut().map_or(0, |f| f(user_data, ctx, off)),
+            Source::Bytes(ptr) => unsafe { *ptr.add(off) },
+        }
+    }
+
+    // Byte of other data through the read fn, e.g. of a baseline
+    fn read_from(&mut self, data: &T, off: usize) -> u8 {
+        self.reads += 1;
+        let ctx = unsafe { &mut *self.ctx };
+        self.read_fn.as_mut().map_or(0, |f| f(data, ctx, off))
+    }
+
+    // Byte as shown to the user, after the display fn
+    fn display(&mut self, off: usize) -> u8 {
+        let d = self.read(off);
+        let ctx = unsafe { &mut *self.ctx };
+        match (&mut self.display_fn, &self.source) {
+            (Some(f), Source::User(user_data)) => f(user_data, ctx, off, d),
+            _ => d,
+        }
+    }
+
+    fn write(&mut self, off: usize, d: u8) -> Result<(), String> {
+        let ctx = unsafe { &mut *self.ctx };
+        match &mut self.source {
+            Source::User(user_data) => {
+                if let Some(f) = self.write_result_fn.as_mut() {
+                    return f(user_data, ctx, off, d);
+                }
+                if let Some(f) = self.write_fn.as_mut() {
+                    f(user_data, ctx, off, d)
+                }
+            }
+            Source::Bytes(ptr) => unsafe { *ptr.add(off) = d },
+        }
+        Ok(())
+    }
+
+    fn highlight(&mut self, off: usize) -> bool {
+        let ctx = unsafe { &mut *self.ctx };
+        match (&mut self.highlight_fn, &self.source) {
+            (Some(f), Source::User(user_data)) => f(user_data, ctx, off),
+            _ => false,
+        }
+    }
+
+    fn user_data(&self) -> Option<&T> {
+        match &self.source {
+            Source::User(user_data) => Some(user_data),
+            Source::Bytes(_) => None,
+        }
+    }
+
+    fn flag(&mut self, off: usize) -> Option<ImColor32> {
+        let ctx = unsafe { &mut *self.ctx };
+        match (&mut self.flag_fn, &self.source) {
+            (Some(f), Source::User(user_data)) => f(user_data, ctx, off),
+            _ => None,
+        }
+    }
+}
+
+// Column the edited byte was picked in.
+// The C++ editor types every edit into the hex cell, including for bytes clicked in the ASCII column.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum EditSource {
+    Hex,
+    Ascii,
+}
+
+// Modifier keys held during a key press, for on_key
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct KeyMods {
+    pub ctrl: bool,
+    pub shift: bool,
+    pub alt: bool,
+    pub super_: bool,
+}
+
+// Expected number of entries for reserve. History never grows past its cap of 32 entries.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct ReserveHints {
+    pub history: usize,
+    pub bookmarks: usize,
+    pub markers: usize,
+}
+
+// What goto_addr does with offsets past the end of the memory
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum OutOfRangePolicy {
+    // Go to the last byte
+    Clamp,
+    Ignore,
+    // Ignore, and report the offset through last_goto_error
+    Error,
+}
+
+// What clicking a cell inside the highlighted range does
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ClickBehavior {
+    // Start editing the cell, the same as any other cell
+    Edit,
+    // Clear the highlighted range
+    ToggleSelect,
+    // Select only the cell, extending the selection from it when dragging with drag select
+    StartRangeSelect,
+}
+
+// Which rows the C++ editor lays out each frame
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RenderMode {
+    // Only the rows in view, through ImGuiListClipper
+    Clipped,
+    // Every row, by making the contents as high as all of them so the clipper has all of them in view.
+    // The window holding the editor scrolls instead, with its own scrollbar when drawn with draw_window.
+    // Rows scrolled out of that window are still clipped by imgui.
+    Eager,
+}
+
+// How byte values are shown in the hex column
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ValueDisplay {
+    Hex,
+    Dec,
+    // value / 255 as a percentage
+    Percent,
+    // value / 255 from 0.0 to 1.0
+    Normalized,
+}
+
+impl ValueDisplay {
+    // None for hex, which is drawn by the C++ editor
+    fn text(self, d: u8) -> Option<String> {
+        match self {
+            ValueDisplay::Hex => None,
+            ValueDisplay::Dec => Some(d.to_string()),
+            ValueDisplay::Percent => Some(format!("{:.0}%", d as f32 / 2.55)),
+            ValueDisplay::Normalized => Some(format!("{:.2}", d as f32 / 255.0)),
+        }
+    }
+}
+
+// Something anchored at an offset, from items_in_range
+#[derive(Debug, Clone, PartialEq)]
+pub enum NavItem<'s> {
+    Bookmark { offset: usize, name: &'s str },
+    Section { offset: usize, label: &'s str },
+    MagicBadge { offset: usize, label: &'s str },
+    Marker { offset: usize, color: ImColor32 },
+}
+
+impl NavItem<'_> {
+    pub fn offset(&self) -> usize {
+        match *self {
+            NavItem::Bookmark { offset, .. }
+            | NavItem::Section { offset, .. }
+            | NavItem::MagicBadge { offset, .. }
+            | NavItem::Marker { offset, .. } => offset,
+        }
+    }
+}
+
+// Additional per-byte column drawn after the ASCII column
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ByteColumn {
+    Hex,
+    Dec,
+    Oct,
+    Char,
+}
+
+impl ByteColumn {
+    // Width of one byte including spacing, in glyphs
+    fn glyphs(self) -> f32 {
+        match self {
+            ByteColumn::Hex => 3.0,
+            ByteColumn::Dec | ByteColumn::Oct => 4.0,
+            ByteColumn::Char => 1.0,
+        }
+    }
+
+    fn text(self, d: u8, upper_case: bool) -> String {
+        match self {
+            ByteColumn::Hex if upper_case => format!("{:02X}", d),
+            ByteColumn::Hex => format!("{:02x}", d),
+            ByteColumn::Dec => format!("{:3}", d),
+            ByteColumn::Oct => format!("{:03o}", d),
+            ByteColumn::Char if (32..127).contains(&d) => (d as char).to_string(),
+            ByteColumn::Char => ".".to_string(),
+        }
+    }
+}
+
+// Radix notation of addresses
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AddrPrefix {
+    None,
+    // 0x1000
+    ZeroX,
+    // $1000
+    Dollar,
+    // 1000h
+    HSuffix,
+}
+
+impl AddrPrefix {
+    fn apply(self, hex: String) -> String {
+        match self {
+            AddrPrefix::None => hex,
+            AddrPrefix::ZeroX => format!("0x{}", hex),
+            AddrPrefix::Dollar => format!("${}", hex),
+            AddrPrefix::HSuffix => format!("{}h", hex),
+        }
+    }
+
+    fn len(self) -> usize {
+        match self {
+            AddrPrefix::None => 0,
+            AddrPrefix::ZeroX => 2,
+            AddrPrefix::Dollar | AddrPrefix::HSuffix => 1,
+        }
+    }
+}
+
+struct MagicBadge {
+    offset: usize,
+    magic: Vec<u8>,
+    label: String,
+}
+
+impl MagicBadge {
+    // Badges that don't fit in the memory never match
+    fn matches(&self, mem_size: usize, mut read: impl FnMut(usize) -> u8) -> bool {
+        match self.offset.checked_add(self.magic.len()) {
+            Some(end) if end <= mem_size => self.magic.iter().zip(self.offset..end).all(|(&b, off)| read(off) == b),
+            _ => false,
+        }
+    }
+}
+
+struct Bookmark {
+    offset: usize,
+    name: String,
+}
+
+struct MaskRule {
+    mask: u8,
+    value: u8,
+    color: ImColor32,
+}
+
+pub struct MemoryEditor<'a, T, C = ()> {
+    read_fn: RefCell<ReadHandler<'a, T, C>>,
+    write_fn: WriteHandler<'a, T, C>,
+    write_result_fn: WriteResultHandler<'a, T, C>,
+    highlight_fn: HighlightHandler<'a, T, C>,
+    display_fn: DisplayHandler<'a, T, C>,
+    flag_fn: FlagHandler<'a, T, C>,
+    // Passed to the handlers alongside the data, set by with_context
+    ctx: RefCell<C>,
+    on_delete: DeleteHandler<'a, T, C>,
+    on_key: KeyHandler<'a, T, C>,
+    // Everything that doesn't involve the context, which with_context moves as is
+    state: EditorState<'a, T>,
+    pub raw: sys::MemoryEditor,
+}
+
+// Settings and state of the editor that don't depend on the context type
+struct EditorState<'a, T> {
     window_name: Option<&'a ImStr>,
-    read_fn: ReadHandler<'a, T>,
-    write_fn: WriteHandler<'a, T>,
-    highlight_fn: HighlightHandler<'a, T>,
+    on_write_error: WriteErrorHandler<'a>,
+    last_write_error: Option<String>,
+    diff_baseline: Option<&'a T>,
+    diff_eq: RefCell<DiffHandler<'a>>,
+    // Whether a byte isn't known yet and is drawn as ??, set by from_channel_source
+    missing_fn: Option<fn(&T, usize) -> bool>,
+    on_open: OpenHandler<'a>,
+    was_open: bool,
+    on_selection_change: SelectionHandler<'a>,
+    copy_format_fn: CopyFormatHandler<'a>,
     mem_size: usize,
     base_addr: usize,
-    pub raw: sys::MemoryEditor,
+    scan_limit: Option<usize>,
+    frame_budget: Option<Duration>,
+    // When the budget of the current frame runs out
+    frame_deadline: Option<Instant>,
+    paging_keys: bool,
+    page_rows: Option<u32>,
+    external_scroll: bool,
+    fixed_rows: Option<u32>,
+    lock_horizontal_scroll: bool,
+    drag_select: bool,
+    click_behavior: ClickBehavior,
+    render_mode: RenderMode,
+    edit_alignment: usize,
+    drag_anchor: Option<usize>,
+    dragging: bool,
+    row_sparkline: bool,
+    extra_columns: Vec<ByteColumn>,
+    overview_bar: bool,
+    magic_badges: Vec<MagicBadge>,
+    crosshair: Option<ImColor32>,
+    cursor_color: Option<ImColor32>,
+    markers: Vec<(usize, ImColor32)>,
+    flat_cells: bool,
+    // Whether the flat_cells colors are pushed for the cell being edited, until the next callback
+    flat_pushed: bool,
+    disabled: bool,
+    // Whether Cols was last set below 1 and clamped
+    cols_clamped: bool,
+    value_display: ValueDisplay,
+    hex_ascii_divider: bool,
+    hex_ascii_gap: Option<f32>,
+    debug_overlay: bool,
+    // Bytes read during the last draw, for the debug overlay
+    last_reads: usize,
+    background_color: Option<ImColor32>,
+    ascii_show_control: bool,
+    mask_rules: Vec<MaskRule>,
+    outlier_threshold: Option<u8>,
+    // Mean of the row being drawn, for highlight_outliers
+    row_mean: f32,
+    allowed_preview_types: Option<Vec<PreviewDataType>>,
+    preview_signed_unsigned: bool,
+    clamp_preview_to_selection: bool,
+    hover_preview: bool,
+    collapsible_footer: bool,
+    // Whether the collapsible footer was open as of the last draw
+    footer_open: bool,
+    sections: BTreeMap<usize, String>,
+    bookmarks: Vec<Bookmark>,
+    relative_to_bookmark: Option<usize>,
+    lock_addr_digits: bool,
+    // Whether OptAddrDigitsCount was set by lock_addr_digits
+    addr_digits_locked: bool,
+    addr_prefix: AddrPrefix,
+    secondary_addr_fn: RefCell<AddrMapHandler<'a>>,
+    // Widest secondary address seen so far, in digits
+    secondary_digits: Cell<usize>,
+    history: VecDeque<usize>,
+    goto_out_of_range: OutOfRangePolicy,
+    last_goto_error: Option<usize>,
+    pending_scroll: Option<f32>,
+    follow_writes: bool,
+    // Offset of the last write since the previous draw
+    last_write: Option<usize>,
+    last_edit_source: Option<EditSource>,
+    // Column the byte being edited was picked in
+    edit_source: EditSource,
+    resize_window: Option<f32>,
+    // Size of the window as of the last draw, if it was drawn
+    window_size: Option<[f32; 2]>,
+    layout: Layout,
 }
 
 impl<'a, T> MemoryEditor<'a, T> {
@@ -29,27 +427,190 @@ impl<'a, T> MemoryEditor<'a, T> {
         let mut raw = Default::default();
         unsafe { sys::Editor_Create(&mut raw) }
         MemoryEditor {
-            window_name: None,
-            read_fn: None,
+            read_fn: RefCell::new(None),
             write_fn: None,
+            write_result_fn: None,
             highlight_fn: None,
-            mem_size: 0,
-            base_addr: 0,
+            display_fn: None,
+            flag_fn: None,
+            ctx: RefCell::new(()),
+            on_delete: None,
+            on_key: None,
+            state: EditorState {
+                window_name: None,
+                on_write_error: None,
+                last_write_error: None,
+                diff_baseline: None,
+                diff_eq: RefCell::new(None),
+                missing_fn: None,
+                on_open: None,
+                was_open: false,
+                on_selection_change: None,
+                copy_format_fn: None,
+                mem_size: 0,
+                base_addr: 0,
+                scan_limit: None,
+                frame_budget: None,
+                frame_deadline: None,
+                paging_keys: false,
+                page_rows: None,
+                external_scroll: false,
+                fixed_rows: None,
+                lock_horizontal_scroll: false,
+                drag_select: false,
+                click_behavior: ClickBehavior::Edit,
+                render_mode: RenderMode::Clipped,
+                edit_alignment: 1,
+                drag_anchor: None,
+                dragging: false,
+                row_sparkline: false,
+                extra_columns: Vec::new(),
+                overview_bar: false,
+                magic_badges: Vec::new(),
+                crosshair: None,
+                cursor_color: None,
+                markers: Vec::new(),
+                flat_cells: false,
+                flat_pushed: false,
+                disabled: false,
+                cols_clamped: false,
+                value_display: ValueDisplay::Hex,
+                hex_ascii_divider: false,
+                hex_ascii_gap: None,
+                debug_overlay: false,
+                last_reads: 0,
+                background_color: None,
+                ascii_show_control: false,
+                mask_rules: Vec::new(),
+                outlier_threshold: None,
+                row_mean: 0.0,
+                allowed_preview_types: None,
+                preview_signed_unsigned: false,
+                clamp_preview_to_selection: false,
+                hover_preview: false,
+                collapsible_footer: false,
+                footer_open: true,
+                sections: BTreeMap::new(),
+                bookmarks: Vec::new(),
+                relative_to_bookmark: None,
+                lock_addr_digits: false,
+                addr_digits_locked: false,
+                addr_prefix: AddrPrefix::None,
+                secondary_addr_fn: RefCell::new(None),
+                secondary_digits: Cell::new(0),
+                history: VecDeque::new(),
+                goto_out_of_range: OutOfRangePolicy::Clamp,
+                last_goto_error: None,
+                pending_scroll: None,
+                follow_writes: false,
+                last_write: None,
+                last_edit_source: None,
+                edit_source: EditSource::Hex,
+                resize_window: None,
+                window_size: None,
+                layout: Default::default(),
+            },
             raw,
         }
     }
 
+    // Context passed to the handlers set with read_fn_ctx and the other *_ctx builders, alongside the data,
+    // e.g. a symbol table when the data is a plain byte slice. Handlers set before this keep working without it.
+    pub fn with_context<C>(self, ctx: C) -> MemoryEditor<'a, T, C> {
+        MemoryEditor {
+            read_fn: RefCell::new(self.read_fn.into_inner().map(|mut f| {
+                Box::new(move |data: &T, _: &mut C, off: usize| f(data, &mut (), off)) as _
+            })),
+            write_fn: self.write_fn.map(|mut f| {
+                Box::new(move |data: &mut T, _: &mut C, off: usize, d: u8| f(data, &mut (), off, d)) as _
+            }),
+            write_result_fn: self.write_result_fn.map(|mut f| {
+                Box::new(move |data: &mut T, _: &mut C, off: usize, d: u8| f(data, &mut (), off, d)) as _
+            }),
+            highlight_fn: self.highlight_fn.map(|mut f| {
+                Box::new(move |data: &T, _: &mut C, off: usize| f(data, &mut (), off)) as _
+            }),
+            display_fn: self.display_fn.map(|mut f| {
+                Box::new(move |data: &T, _: &mut C, off: usize, d: u8| f(data, &mut (), off, d)) as _
+            }),
+            flag_fn: self.flag_fn.map(|mut f| {
+                Box::new(move |data: &T, _: &mut C, off: usize| f(data, &mut (), off)) as _
+            }),
+            ctx: RefCell::new(ctx),
+            on_delete: self.on_delete.map(|mut f| {
+                Box::new(move |data: &mut T, _: &mut C, range: Range<usize>| f(data, &mut (), range)) as _
+            }),
+            on_key: self.on_key.map(|mut f| {
+                Box::new(move |data: &mut T, _: &mut C, key: Key, mods: &KeyMods, selection: Option<Range<usize>>| {
+                    f(data, &mut (), key, mods, selection)
+                }) as _
+            }),
+            state: self.state,
+            raw: self.raw,
+        }
+    }
+}
+
+impl<'a, T, C> MemoryEditor<'a, T, C> {
+    // Reset everything to the same state as new(), including handlers, annotations and history.
+    // The context is kept, since there is no new one to reset it to.
+    pub fn clear_all(&mut self) {
+        let MemoryEditor { state, raw, .. } = MemoryEditor::<T>::new();
+        *self.read_fn.get_mut() = None;
+        self.write_fn = None;
+        self.write_result_fn = None;
+        self.highlight_fn = None;
+        self.display_fn = None;
+        self.flag_fn = None;
+        self.on_delete = None;
+        self.on_key = None;
+        self.state = state;
+        self.raw = raw;
+    }
+
+    // Context set with with_context, e.g. to update it between draws
+    #[inline]
+    pub fn context_mut(&mut self) -> &mut C {
+        self.ctx.get_mut()
+    }
+
+    // Preallocate the editor's own buffers, so adding entries doesn't reallocate mid-frame.
+    // The read cache of a ChannelSource is reserved on the source itself.
+    pub fn reserve(&mut self, hint: ReserveHints) {
+        let history = hint.history.min(HISTORY_LEN).saturating_sub(self.state.history.len());
+        self.state.history.reserve(history);
+        self.state.bookmarks.reserve(hint.bookmarks.saturating_sub(self.state.bookmarks.len()));
+        self.state.markers.reserve(hint.markers.saturating_sub(self.state.markers.len()));
+    }
+
     // Size of memory in bytes (Automatically set if using bytes)
     #[inline]
     pub fn mem_size(mut self, mem_size: usize) -> Self {
-        self.mem_size = mem_size;
+        self.state.mem_size = mem_size;
         self
     }
 
     // The base addr displayed
     #[inline]
     pub fn base_addr(mut self, base_addr: usize) -> Self {
-        self.base_addr = base_addr;
+        self.state.base_addr = base_addr;
+        self
+    }
+
+    // Maximum number of bytes visited by methods that scan the whole memory (e.g. diff_count)
+    #[inline]
+    pub fn scan_limit(mut self, scan_limit: Option<usize>) -> Self {
+        self.state.scan_limit = scan_limit;
+        self
+    }
+    // stop reading bytes once drawing the contents takes longer than ms in a frame. the remaining cells of the frame
+    // are drawn empty with "…" at the start of each row, and the handlers aren't called for them.
+    // ms must be finite and not negative.
+    #[inline]
+    pub fn frame_budget(mut self, ms: f32) -> Self {
+        assert!(ms.is_finite() && ms >= 0.0, "Frame budget must be a finite, non-negative number of ms");
+        // Budgets past u32::MAX seconds never run out anyway, and much larger ones don't fit in a Duration
+        self.state.frame_budget = Some(Duration::from_secs_f32((ms / 1000.0).min(u32::MAX as f32)));
         self
     }
 
@@ -64,10 +625,39 @@ impl<'a, T> MemoryEditor<'a, T> {
         self.raw.ReadOnly = read_only;
         self
     }
-    // number of columns to display.
+    // radix prefix or suffix of addresses, e.g. 0x1000 or 1000h.
+    #[inline]
+    pub fn addr_prefix(mut self, prefix: AddrPrefix) -> Self {
+        self.state.addr_prefix = prefix;
+        self
+    }
+    // show a second address next to each row's address, e.g. the physical address of a virtual one.
+    // f is given the row's offset. it only affects the address column, reads still use the offset.
+    #[inline]
+    pub fn secondary_addr_fn<F>(mut self, f: F) -> Self
+    where
+        F: FnMut(usize) -> usize + 'a,
+    {
+        self.state.secondary_addr_fn = RefCell::new(Some(Box::new(f)));
+        self
+    }
+    // number of columns to display. values below 1 are clamped to 1, which cols_clamped reports.
     #[inline]
     pub fn cols(mut self, cols: i32) -> Self {
-        self.raw.Cols = cols;
+        self.raw.Cols = cols.max(1);
+        self.state.cols_clamped = cols < 1;
+        self
+    }
+    // grey out the contents and block all interaction with them, including selection.
+    #[inline]
+    pub fn disabled(mut self, disabled: bool) -> Self {
+        self.state.disabled = disabled;
+        self
+    }
+    // scroll to keep the last written byte visible. writes made outside of the editor can be reported with notify_write.
+    #[inline]
+    pub fn follow_writes(mut self, enable: bool) -> Self {
+        self.state.follow_writes = enable;
         self
     }
     // display options button/context menu. when disabled, options will be locked unless you provide your own UI for them.
@@ -82,6 +672,52 @@ impl<'a, T> MemoryEditor<'a, T> {
         self.raw.OptShowDataPreview = show_data_preview;
         self
     }
+    // data type the preview interprets the selected bytes as.
+    #[inline]
+    pub fn preview_data_type(mut self, ty: PreviewDataType) -> Self {
+        assert!(
+            self.preview_types().contains(&ty),
+            "Preview data type must be one of the allowed preview types"
+        );
+        self.raw.PreviewDataType = ty as sys::ImGuiDataType;
+        self
+    }
+    // restrict the data types selectable in the preview. the preview is drawn by this crate instead of the C++ editor.
+    #[inline]
+    pub fn allowed_preview_types(mut self, types: &[PreviewDataType]) -> Self {
+        assert!(!types.is_empty(), "At least one preview type must be allowed");
+        if !types.iter().any(|&ty| ty as sys::ImGuiDataType == self.raw.PreviewDataType) {
+            self.raw.PreviewDataType = types[0] as sys::ImGuiDataType;
+        }
+        self.state.allowed_preview_types = Some(types.to_vec());
+        self
+    }
+    // show integer previews as both signed and unsigned. the preview is drawn by this crate instead of the C++ editor.
+    #[inline]
+    pub fn preview_signed_unsigned(mut self, show: bool) -> Self {
+        self.state.preview_signed_unsigned = show;
+        self
+    }
+    // when previewing inside the highlighted range, don't read past its end.
+    // types too large for the rest of the range show "selection too short" instead. the preview is drawn by this crate.
+    #[inline]
+    pub fn clamp_preview_to_selection(mut self, clamp: bool) -> Self {
+        self.state.clamp_preview_to_selection = clamp;
+        self
+    }
+    // tooltip showing the bytes at the hovered cell as every preview type, in the preview's endianess.
+    #[inline]
+    pub fn hover_preview(mut self, enable: bool) -> Self {
+        self.state.hover_preview = enable;
+        self
+    }
+    // put the data preview under a collapsing header. rows take up its space while collapsed.
+    // the preview is drawn by this crate.
+    #[inline]
+    pub fn collapsible_footer(mut self, collapsible: bool) -> Self {
+        self.state.collapsible_footer = collapsible;
+        self
+    }
     // display values in HexII representation instead of regular hexadecimal: hide null/zero bytes, ascii values as ".X".
     #[inline]
     pub fn show_hexii(mut self, show_hexii: bool) -> Self {
@@ -94,6 +730,27 @@ impl<'a, T> MemoryEditor<'a, T> {
         self.raw.OptShowAscii = show_ascii;
         self
     }
+    // display tabs, newlines and carriage returns as arrows in the ASCII column instead of '.'.
+    // the font needs to include the arrows (U+2190..U+21FF).
+    #[inline]
+    pub fn ascii_show_control(mut self, show: bool) -> Self {
+        self.state.ascii_show_control = show;
+        self
+    }
+    // show bytes in the hex column as decimal, a percentage or a normalized float. editing still types hex.
+    // values too wide for a cell are drawn with a smaller font. takes precedence over HexII.
+    #[inline]
+    pub fn value_display(mut self, mode: ValueDisplay) -> Self {
+        self.state.value_display = mode;
+        self
+    }
+    // space between the hex and ASCII columns, optionally with a vertical rule. the gap can only be widened.
+    #[inline]
+    pub fn hex_ascii_divider(mut self, show: bool, gap_px: f32) -> Self {
+        self.state.hex_ascii_divider = show;
+        self.state.hex_ascii_gap = Some(gap_px);
+        self
+    }
     // display null/zero bytes using the TextDisabled color.
     #[inline]
     pub fn grey_out_zeroes(mut self, grey_out_zeroes: bool) -> Self {
@@ -116,6 +773,18 @@ impl<'a, T> MemoryEditor<'a, T> {
     #[inline]
     pub fn addr_digits_count(mut self, addr_digits_count: i32) -> Self {
         self.raw.OptAddrDigitsCount = addr_digits_count;
+        self.state.addr_digits_locked = false;
+        self
+    }
+    // keep the number of address digits computed on the next draw, instead of recomputing it when mem size changes.
+    // has no effect while addr digits count is set.
+    #[inline]
+    pub fn lock_addr_digits(mut self, lock: bool) -> Self {
+        if !lock && self.state.addr_digits_locked {
+            self.raw.OptAddrDigitsCount = 0;
+            self.state.addr_digits_locked = false;
+        }
+        self.state.lock_addr_digits = lock;
         self
     }
     // background color of highlighted bytes.
@@ -124,121 +793,2447 @@ impl<'a, T> MemoryEditor<'a, T> {
         self.raw.HighlightColor = color.into();
         self
     }
+    // tint the row and column of the hovered cell.
+    #[inline]
+    pub fn crosshair(mut self, enable: bool, color: ImColor32) -> Self {
+        self.state.crosshair = if enable { Some(color) } else { None };
+        self
+    }
+    // color drawn over the cell being edited, to tell it apart from highlighted cells. should be translucent,
+    // since it is drawn on top of the input.
+    #[inline]
+    pub fn cursor_color(mut self, color: ImColor32) -> Self {
+        self.state.cursor_color = Some(color);
+        self
+    }
+    // don't tint the cell being edited while hovered/active, so the highlight colors show as is.
+    // the options and preview below the cells keep their tints.
+    #[inline]
+    pub fn flat_cells(mut self, flat: bool) -> Self {
+        self.state.flat_cells = flat;
+        self
+    }
+    // show frame rate, bytes read during the previous frame, visible rows and scroll position over the contents.
+    // reads count every byte read through the read fn, including the ones for the preview, badges and copying.
+    #[inline]
+    pub fn debug_overlay(mut self, show: bool) -> Self {
+        self.state.debug_overlay = show;
+        self
+    }
+    // background color of the hex/ascii area. defaults to the current theme.
+    #[inline]
+    pub fn background_color(mut self, color: ImColor32) -> Self {
+        self.state.background_color = Some(color);
+        self
+    }
+    // tint bytes that differ from the mean of their row by more than threshold.
+    // reads every visible byte twice more per frame, once for the row means and once for the comparison.
+    #[inline]
+    pub fn highlight_outliers(mut self, enable: bool, threshold: u8) -> Self {
+        self.state.outlier_threshold = if enable { Some(threshold) } else { None };
+        self
+    }
+    // highlight bytes where (byte & mask) == value. can be called multiple times, the first matching rule is used.
+    #[inline]
+    pub fn highlight_mask(mut self, mask: u8, value: u8, color: ImColor32) -> Self {
+        self.state.mask_rules.push(MaskRule { mask, value, color });
+        self
+    }
     // optional handler to read bytes.
     #[inline]
-    pub fn read_fn<F>(mut self, read_fn: F) -> Self where F: FnMut(&T, usize) -> u8 + 'a {
-        self.read_fn = Some(Box::new(read_fn));
+    pub fn read_fn<F>(mut self, mut read_fn: F) -> Self where F: FnMut(&T, usize) -> u8 + 'a {
+        self.read_fn = RefCell::new(Some(Box::new(move |data: &T, _: &mut C, off: usize| read_fn(data, off))));
+        self
+    }
+    // read fn that is also given the context.
+    #[inline]
+    pub fn read_fn_ctx<F>(mut self, read_fn: F) -> Self where F: FnMut(&T, &mut C, usize) -> u8 + 'a {
+        self.read_fn = RefCell::new(Some(Box::new(read_fn)));
         self
     }
     // optional handler to write bytes.
     #[inline]
-    pub fn write_fn<F>(mut self, write_fn: F) -> Self where F: FnMut(&mut T, usize, u8) + 'a {
+    pub fn write_fn<F>(mut self, mut write_fn: F) -> Self where F: FnMut(&mut T, usize, u8) + 'a {
+        self.write_fn = Some(Box::new(move |data: &mut T, _: &mut C, off: usize, d: u8| write_fn(data, off, d)));
+        self
+    }
+    // write fn that is also given the context.
+    #[inline]
+    pub fn write_fn_ctx<F>(mut self, write_fn: F) -> Self where F: FnMut(&mut T, &mut C, usize, u8) + 'a {
         self.write_fn = Some(Box::new(write_fn));
         self
     }
+    // optional handler to write bytes that can fail. used instead of the write fn when set.
+    // errors are available from last_write_error until the next successful write.
+    #[inline]
+    pub fn write_result_fn<F>(mut self, mut write_result_fn: F) -> Self
+    where
+        F: FnMut(&mut T, usize, u8) -> Result<(), String> + 'a,
+    {
+        self.write_result_fn =
+            Some(Box::new(move |data: &mut T, _: &mut C, off: usize, d: u8| write_result_fn(data, off, d)));
+        self
+    }
+    // write result fn that is also given the context.
+    #[inline]
+    pub fn write_result_fn_ctx<F>(mut self, write_result_fn: F) -> Self
+    where
+        F: FnMut(&mut T, &mut C, usize, u8) -> Result<(), String> + 'a,
+    {
+        self.write_result_fn = Some(Box::new(write_result_fn));
+        self
+    }
+    // optional handler called with the offset and error when the write result fn fails.
+    #[inline]
+    pub fn on_write_error<F>(mut self, on_write_error: F) -> Self where F: FnMut(usize, &str) + 'a {
+        self.state.on_write_error = Some(Box::new(on_write_error));
+        self
+    }
     // optional handler to return Highlight property (to support non-contiguous highlighting).
     #[inline]
-    pub fn highlight_fn<F>(mut self, highlight_fn: F) -> Self where F: FnMut(&T, usize) -> bool + 'a {
+    pub fn highlight_fn<F>(mut self, mut highlight_fn: F) -> Self where F: FnMut(&T, usize) -> bool + 'a {
+        self.highlight_fn = Some(Box::new(move |data: &T, _: &mut C, off: usize| highlight_fn(data, off)));
+        self
+    }
+    // highlight fn that is also given the context.
+    #[inline]
+    pub fn highlight_fn_ctx<F>(mut self, highlight_fn: F) -> Self where F: FnMut(&T, &mut C, usize) -> bool + 'a {
         self.highlight_fn = Some(Box::new(highlight_fn));
         self
     }
 
-    // When drawing, create a window with this name
+    // what goto_addr does with offsets past the end of the memory. clamps by default.
     #[inline]
-    pub fn draw_window(mut self, window_name: &'a ImStr) -> Self {
-        self.window_name = Some(window_name);
+    pub fn goto_out_of_range(mut self, policy: OutOfRangePolicy) -> Self {
+        self.state.goto_out_of_range = policy;
         self
     }
-    // No longer create a window when drawing
+    // PageUp/PageDown scroll by a page and Home/End jump to the ends while the editor is focused.
     #[inline]
-    pub fn no_window(mut self) -> Self {
-        self.window_name = None;
+    pub fn enable_paging_keys(mut self, enable: bool) -> Self {
+        self.state.paging_keys = enable;
         self
     }
-
-    // Draw the memory editor with read and write functions set
-    pub fn draw(&mut self, _: &Ui, user_data: &mut T) {
-        assert!(
-            self.read_fn.is_some() || self.mem_size == 0,
-            "Read Fn must be set if mem size > 0"
-        );
-        assert!(
-            self.write_fn.is_some() || self.raw.ReadOnly || self.mem_size == 0,
-            "Write Fn must be set if not read only and mem size > 0"
-        );
-        self.raw.ReadFn = Some(read_wrapper::<T>);
-        self.raw.WriteFn = Some(write_wrapper::<T>);
-        self.raw.HighlightFn = if self.highlight_fn.is_some() { Some(highlight_wrapper::<T>) } else { None };
-
-        let mut data = (
-            &mut self.read_fn,
-            &mut self.write_fn,
-            &mut self.highlight_fn,
-            user_data
-        );
-        let mem_data = &mut data as *mut MemData<T> as *mut c_void;
-        unsafe { self.draw_raw(mem_data) }
+    // rows moved by PageUp/PageDown. None moves by the number of visible rows.
+    #[inline]
+    pub fn page_rows(mut self, rows: Option<u32>) -> Self {
+        self.state.page_rows = rows;
+        self
     }
 
-    pub unsafe fn draw_raw(&mut self, mem_data: *mut c_void) {
-        if let Some(title) = self.window_name {
-            sys::Editor_DrawWindow(
-                &mut self.raw,
-                title.as_ptr(),
-                mem_data,
-                self.mem_size,
-                self.base_addr,
-            );
-        } else {
-            sys::Editor_DrawContents(
-                &mut self.raw,
-                mem_data,
-                self.mem_size,
-                self.base_addr,
-            );
-        }
+    // make the contents exactly rows rows high, without a scrollbar. scroll with goto_addr or set_scroll_fraction.
+    #[inline]
+    pub fn fixed_rows(mut self, rows: u32) -> Self {
+        self.state.fixed_rows = Some(rows);
+        self
     }
-}
-
-impl<'a> MemoryEditor<'a, &[u8]> {
-    pub fn draw_vec(&mut self, _: &Ui, data: &[u8]) {
-        assert!(!self.raw.ReadOnly, "Data muse be a mutable slice if editor is not read only");
-        // TODO: Support highlight fn
-        assert!(
-            self.read_fn.is_none() && self.write_fn.is_none() && self.highlight_fn.is_none(),
-            "Handler functions not supported when using draw_vec. Use draw instead"
-        );
-        self.mem_size = data.len();
-        unsafe { self.draw_raw(data.as_ptr() as *mut c_void) }
+    // keep the contents scrolled all the way left, clipping columns that don't fit.
+    #[inline]
+    pub fn lock_horizontal_scroll(mut self, lock: bool) -> Self {
+        self.state.lock_horizontal_scroll = lock;
+        self
+    }
+    // only allow editing bytes at offsets that are multiples of align, which are marked with a bar on their left.
+    // editing still types one byte at a time, so only the first byte of each aligned group can be changed.
+    // other bytes can't be typed into, but Delete still clears all of the selection.
+    #[inline]
+    pub fn edit_alignment(mut self, align: usize) -> Self {
+        self.state.edit_alignment = align.max(1);
+        self
+    }
+    // what clicking a cell inside the highlighted range does.
+    #[inline]
+    pub fn click_behavior(mut self, behavior: ClickBehavior) -> Self {
+        self.state.click_behavior = behavior;
+        self
+    }
+    // lay out every row instead of only the ones in view, so read, highlight and display fns see every byte and
+    // overlays can use any row. the contents are sized to all rows, overriding fixed_rows, so put the editor in a
+    // window tall enough for them. meant for small memory, up to a few KiB. the cost grows with mem size, since all
+    // of it is read and drawn every frame (a 64 KiB buffer is already 4096 rows), so leave large memory Clipped.
+    #[inline]
+    pub fn render_mode(mut self, mode: RenderMode) -> Self {
+        self.state.render_mode = mode;
+        self
+    }
+    // hide the scrollbar, for scrolling with your own widget through scroll_fraction and set_scroll_fraction.
+    // the mouse wheel still scrolls, so read scroll_fraction back every frame.
+    #[inline]
+    pub fn external_scroll(mut self, enable: bool) -> Self {
+        self.state.external_scroll = enable;
+        self
     }
-}
 
+    // click and drag to select a range of bytes. scrolls when dragging past the top or bottom.
+    #[inline]
+    pub fn enable_drag_select(mut self, enable: bool) -> Self {
+        self.state.drag_select = enable;
+        self
+    }
 
-// Convenience implementations
-impl<'a> MemoryEditor<'a, &mut [u8]> {
-    pub fn draw_vec(&mut self, _: &Ui, data: &mut [u8]) {
-        // TODO: Support highlight fn
-        assert!(
-            self.read_fn.is_none() && self.write_fn.is_none() && self.highlight_fn.is_none(),
-            "Handler functions not supported when using draw_vec. Use draw instead"
-        );
-        self.mem_size = data.len();
-        unsafe { self.draw_raw(data.as_mut_ptr() as *mut c_void) }
+    // draw a small line plot of each row's values (0-255) to the right of the ASCII column.
+    // windows created with draw_window are made wider to fit it. when drawing into your own window, leave room for it.
+    #[inline]
+    pub fn row_sparkline(mut self, enable: bool) -> Self {
+        self.state.row_sparkline = enable;
+        self
+    }
+    // more columns showing each byte, drawn after the ASCII column (and the row sparkline) in the given order.
+    #[inline]
+    pub fn extra_columns(mut self, cols: &[ByteColumn]) -> Self {
+        self.state.extra_columns = cols.to_vec();
+        self
+    }
+    // thin bar along the right edge showing the visible part of the whole memory, with ticks for bookmarks
+    // and sections. clicking it goes to that part of the memory.
+    #[inline]
+    pub fn show_overview_bar(mut self, show: bool) -> Self {
+        self.state.overview_bar = show;
+        self
     }
-}
 
-// These shouldn't get called if no fn is set
-unsafe extern "C" fn read_wrapper<'a, T>(data: *const u8, off: usize) -> u8 {
-    let (read_fn, _, _, user_data) = &mut *(data as *mut MemData<T>);
-    read_fn.as_mut().unwrap()(user_data, off)
+    // show label above the contents when the bytes at offset equal magic. can be called multiple times.
+    #[inline]
+    pub fn magic_badge(mut self, offset: usize, magic: Vec<u8>, label: String) -> Self {
+        self.state.magic_badges.push(MagicBadge { offset, magic, label });
+        self
+    }
+    // optional handler to transform bytes for display only (hex, ascii, preview).
+    // editing still types raw bytes which are passed as is to the write fn, not display-transformed ones.
+    #[inline]
+    pub fn display_fn<F>(mut self, mut display_fn: F) -> Self where F: FnMut(&T, usize, u8) -> u8 + 'a {
+        self.display_fn = Some(Box::new(move |data: &T, _: &mut C, off: usize, d: u8| display_fn(data, off, d)));
+        self
+    }
+    // display fn that is also given the context.
+    #[inline]
+    pub fn display_fn_ctx<F>(mut self, display_fn: F) -> Self where F: FnMut(&T, &mut C, usize, u8) -> u8 + 'a {
+        self.display_fn = Some(Box::new(display_fn));
+        self
+    }
+    // optional handler returning the color of a small flag drawn in the top right corner of a cell,
+    // e.g. for breakpoints. drawn on top of highlighting.
+    #[inline]
+    pub fn flag_fn<F>(mut self, mut flag_fn: F) -> Self where F: FnMut(&T, usize) -> Option<ImColor32> + 'a {
+        self.flag_fn = Some(Box::new(move |data: &T, _: &mut C, off: usize| flag_fn(data, off)));
+        self
+    }
+    // flag fn that is also given the context.
+    #[inline]
+    pub fn flag_fn_ctx<F>(mut self, flag_fn: F) -> Self
+    where
+        F: FnMut(&T, &mut C, usize) -> Option<ImColor32> + 'a,
+    {
+        self.flag_fn = Some(Box::new(flag_fn));
+        self
+    }
+    // highlight bytes that differ from baseline, which is read through the read fn.
+    // eq is given the offset and the bytes of the data and the baseline, and returns whether they count as equal.
+    #[inline]
+    pub fn diff_with<F>(mut self, baseline: &'a T, eq: F) -> Self where F: FnMut(usize, u8, u8) -> bool + 'a {
+        self.state.diff_baseline = Some(baseline);
+        self.state.diff_eq = RefCell::new(Some(Box::new(eq)));
+        self
+    }
+    // optional handler called when the window is drawn after not being open, i.e. on the first draw_window
+    // and every time it is drawn again after being closed. called before the window begins so it can e.g. set focus.
+    #[inline]
+    pub fn on_open<F>(mut self, on_open: F) -> Self where F: FnMut() + 'a {
+        self.state.on_open = Some(Box::new(on_open));
+        self
+    }
+    // optional handler called with the new range when the selection changes.
+    #[inline]
+    pub fn on_selection_change<F>(mut self, on_selection_change: F) -> Self where F: FnMut(Range<usize>) + 'a {
+        self.state.on_selection_change = Some(Box::new(on_selection_change));
+        self
+    }
+    // optional handler called when Delete or Backspace is pressed with a selection. when not set, the selection is
+    // filled with zeroes through the write fn unless read only. not called when drawing bytes directly.
+    #[inline]
+    pub fn on_delete<F>(mut self, mut on_delete: F) -> Self where F: FnMut(&mut T, Range<usize>) + 'a {
+        self.on_delete = Some(Box::new(move |data: &mut T, _: &mut C, range: Range<usize>| on_delete(data, range)));
+        self
+    }
+    // on delete handler that is also given the context.
+    #[inline]
+    pub fn on_delete_ctx<F>(mut self, on_delete: F) -> Self where F: FnMut(&mut T, &mut C, Range<usize>) + 'a {
+        self.on_delete = Some(Box::new(on_delete));
+        self
+    }
+    // optional handler formatting the highlighted bytes copied with Ctrl+C. defaults to space separated hex.
+    #[inline]
+    pub fn copy_format_fn<F>(mut self, copy_format_fn: F) -> Self where F: FnMut(&[u8]) -> String + 'a {
+        self.state.copy_format_fn = Some(Box::new(copy_format_fn));
+        self
+    }
+    // optional handler called for every key pressed while the editor is focused and no cell is being edited,
+    // with the selection if any. not called when drawing bytes directly.
+    // only the keys in imgui's Key enum are reported: tab, arrows, navigation, editing keys, space, enter and escape,
+    // and of the letters only A, C, V, X, Y and Z. function keys and other letters can't be bound.
+    #[inline]
+    pub fn on_key<F>(mut self, mut on_key: F) -> Self
+    where
+        F: FnMut(&mut T, Key, &KeyMods, Option<Range<usize>>) + 'a,
+    {
+        self.on_key = Some(Box::new(move |data: &mut T, _: &mut C, key: Key, mods: &KeyMods, selection| {
+            on_key(data, key, mods, selection)
+        }));
+        self
+    }
+    // on key handler that is also given the context.
+    #[inline]
+    pub fn on_key_ctx<F>(mut self, on_key: F) -> Self
+    where
+        F: FnMut(&mut T, &mut C, Key, &KeyMods, Option<Range<usize>>) + 'a,
+    {
+        self.on_key = Some(Box::new(on_key));
+        self
+    }
+
+    // When drawing, create a window with this name
+    #[inline]
+    pub fn draw_window(mut self, window_name: &'a ImStr) -> Self {
+        self.state.window_name = Some(window_name);
+        self
+    }
+    // No longer create a window when drawing
+    #[inline]
+    pub fn no_window(mut self) -> Self {
+        self.state.window_name = None;
+        self
+    }
+
+    // Label the row containing offset as the start of a section.
+    // The C++ editor's clipper can't make room for extra rows, so the section is drawn as a rule above the row
+    // with its label to the right of the ASCII column, after any sparkline and extra columns.
+    // windows created with draw_window are made wide enough for the labels.
+    pub fn add_section(&mut self, offset: usize, label: String) {
+        self.state.sections.insert(offset, label);
+    }
+
+    pub fn clear_sections(&mut self) {
+        self.state.sections.clear();
+    }
+
+    // Scroll to and start editing the byte at offset on the next draw, remembering it in the history
+    pub fn goto_addr(&mut self, offset: usize) {
+        let offset = if offset < self.state.mem_size {
+            offset
+        } else {
+            match self.state.goto_out_of_range {
+                OutOfRangePolicy::Clamp if self.state.mem_size > 0 => self.state.mem_size - 1,
+                OutOfRangePolicy::Error => {
+                    self.state.last_goto_error = Some(offset);
+                    return;
+                }
+                _ => return,
+            }
+        };
+        self.state.last_goto_error = None;
+        self.raw.GotoAddr = offset;
+        self.state.edit_source = EditSource::Hex;
+        self.state.history.retain(|&addr| addr != offset);
+        self.state.history.push_front(offset);
+        self.state.history.truncate(HISTORY_LEN);
+    }
+
+    // Error of the last write if it failed
+    #[inline]
+    pub fn last_write_error(&self) -> Option<&str> {
+        self.state.last_write_error.as_deref()
+    }
+
+    // Column the byte of the most recent write was picked in, None if it wasn't typed in
+    #[inline]
+    pub fn last_edit_source(&self) -> Option<EditSource> {
+        self.state.last_edit_source
+    }
+
+    // Report a write made outside of the editor, for follow_writes
+    #[inline]
+    pub fn notify_write(&mut self, offset: usize) {
+        self.state.last_write = Some(offset);
+        self.state.last_edit_source = None;
+    }
+
+    // Offset of the last goto rejected by OutOfRangePolicy::Error, until the next goto that isn't
+    #[inline]
+    pub fn last_goto_error(&self) -> Option<usize> {
+        self.state.last_goto_error
+    }
+
+    // Most recently visited offsets first
+    #[inline]
+    pub fn history(&self) -> impl Iterator<Item = usize> + '_ {
+        self.state.history.iter().copied()
+    }
+
+    // Combo box of recently visited addresses. Selecting one goes to it.
+    pub fn draw_history_dropdown(&mut self, ui: &Ui) {
+        let preview = ImString::new(self.state.history.front().map_or(String::new(), |&addr| self.history_label(addr)));
+        let mut selected = None;
+        if let Some(token) = ComboBox::new(im_str!("History##memory_editor")).preview_value(&preview).begin(ui) {
+            for (i, &addr) in self.state.history.iter().enumerate() {
+                let label = ImString::new(format!("{}##{}", self.history_label(addr), i));
+                if Selectable::new(&label).build(ui) {
+                    selected = Some(addr);
+                }
+            }
+            token.end(ui);
+        }
+        if let Some(addr) = selected {
+            self.goto_addr(addr);
+        }
+    }
+
+    // Addresses are relative to the bookmark the address column is relative to, if any.
+    // Followed by the names of the bookmarks at the address and the section it is in.
+    fn history_label(&self, off: usize) -> String {
+        let addr = match self.state.relative_to_bookmark {
+            Some(index) => self.bookmark_relative_addr(index, off),
+            None => self.format_addr(off),
+        };
+        let bookmarks = self.state.bookmarks.iter().filter(|bookmark| bookmark.offset == off);
+        let section = self.state.sections.range(..=off).next_back().map(|(_, section)| section.as_str());
+        let names: Vec<&str> = bookmarks.map(|bookmark| bookmark.name.as_str()).chain(section).collect();
+        if names.is_empty() {
+            addr
+        } else {
+            format!("{} ({})", addr, names.join(", "))
+        }
+    }
+
+    // Address as displayed in the address column
+    fn format_addr(&self, off: usize) -> String {
+        let digits = layout::calc_sizes(&self.raw, self.state.mem_size, self.state.base_addr).AddrDigitsCount as usize;
+        self.state.addr_prefix.apply(self.format_hex(self.state.base_addr + off, digits))
+    }
+
+    fn format_hex(&self, addr: usize, digits: usize) -> String {
+        if self.raw.OptUpperCaseHex {
+            format!("{:01$X}", addr, digits)
+        } else {
+            format!("{:01$x}", addr, digits)
+        }
+    }
+
+    // Overview of the whole memory as cell_px sized squares colored by byte category
+    // (zero, 0xFF, printable ASCII, other ASCII, everything else). Clicking a square goes to it in the editor.
+    pub fn draw_byteview(&mut self, ui: &Ui, data: &T, cell_px: f32) {
+        let cell_px = cell_px.max(1.0);
+        let mut clicked = None;
+        ChildWindow::new(im_str!("##byteview")).build(ui, || {
+            let per_row = ((ui.content_region_avail()[0] / cell_px) as usize).max(1);
+            let rows = (self.state.mem_size + per_row - 1) / per_row;
+            let origin = ui.cursor_screen_pos();
+            let first_row = (ui.scroll_y() / cell_px) as usize;
+            let last_row = (((ui.scroll_y() + ui.window_size()[1]) / cell_px) as usize + 1).min(rows);
+
+            {
+                let mut read_fn = self.read_fn.borrow_mut();
+                let read_fn = read_fn.as_mut().expect("Read Fn must be set to draw the byteview");
+                let mut ctx = self.ctx.borrow_mut();
+                let draw_list = ui.get_window_draw_list();
+                for row in first_row..last_row {
+                    for col in 0..per_row {
+                        let off = row * per_row + col;
+                        if off >= self.state.mem_size {
+                            break;
+                        }
+                        let min = [origin[0] + col as f32 * cell_px, origin[1] + row as f32 * cell_px];
+                        let max = [min[0] + cell_px, min[1] + cell_px];
+                        draw_list.add_rect(min, max, byteview_color(read_fn(data, &mut ctx, off))).filled(true).build();
+                    }
+                }
+            }
+            ui.dummy([per_row as f32 * cell_px, rows as f32 * cell_px]);
+
+            if ui.is_window_hovered() {
+                let [x, y] = ui.io().mouse_pos;
+                let col = ((x - origin[0]) / cell_px) as usize;
+                let off = ((y - origin[1]) / cell_px) as usize * per_row + col;
+                if x >= origin[0] && y >= origin[1] && col < per_row && off < self.state.mem_size {
+                    ui.tooltip_text(self.format_addr(off));
+                    if ui.is_mouse_clicked(MouseButton::Left) {
+                        clicked = Some(off);
+                    }
+                }
+            }
+        });
+        if let Some(off) = clicked {
+            self.goto_addr(off);
+        }
+    }
+
+    // Base, mine and theirs side by side for merging. Bytes changed from base get the highlight color as background,
+    // and bytes changed differently in mine and theirs are conflicts. Clicking a byte in the theirs panel takes it
+    // into mine, unless read only. Mine already holds its own bytes, so clicks in the base and mine panels do nothing.
+    // Uses cols, base addr and the hex case of the editor.
+    pub fn draw_three_way(&mut self, ui: &Ui, base: &[u8], mine: &mut [u8], theirs: &[u8]) {
+        assert!(
+            base.len() == mine.len() && mine.len() == theirs.len(),
+            "Three way panels must all be the same length"
+        );
+        let cols = self.raw.Cols.max(1) as usize;
+        let digits = self.addr_digits(mine.len());
+        let glyph_width = ui.calc_text_size(im_str!("F"), false, -1.0)[0];
+        let line_height = ui.text_line_height();
+        let addr_width = (digits + 2) as f32 * glyph_width;
+        let panel_width = (cols * 3 + 1) as f32 * glyph_width;
+        let changed_color = ImColor32::from_bits(self.raw.HighlightColor);
+        let conflict_color = ImColor32::from_rgba(255, 64, 64, 96);
+
+        for (i, name) in [im_str!("base"), im_str!("mine"), im_str!("theirs")].iter().enumerate() {
+            if i > 0 {
+                ui.same_line(addr_width + i as f32 * panel_width);
+            } else {
+                ui.set_cursor_pos([addr_width, ui.cursor_pos()[1]]);
+            }
+            ui.text(name);
+        }
+
+        let mut take = None;
+        ChildWindow::new(im_str!("##three_way")).build(ui, || {
+            let rows = (mine.len() + cols - 1) / cols;
+            let origin = ui.cursor_screen_pos();
+            let first_row = (ui.scroll_y() / line_height) as usize;
+            let last_row = (((ui.scroll_y() + ui.window_size()[1]) / line_height) as usize + 1).min(rows);
+            let panels: [&[u8]; 3] = [base, mine, theirs];
+            let cell_pos = |panel: usize, off: usize| {
+                [
+                    origin[0] + addr_width + panel as f32 * panel_width + (off % cols * 3) as f32 * glyph_width,
+                    origin[1] + (off / cols) as f32 * line_height,
+                ]
+            };
+
+            let draw_list = ui.get_window_draw_list();
+            let text_color = ui.style_color(StyleColor::Text);
+            for row in first_row..last_row {
+                let y = origin[1] + row as f32 * line_height;
+                let addr = self.format_hex(self.state.base_addr + row * cols, digits) + ":";
+                draw_list.add_text([origin[0], y], text_color, &addr);
+                for off in (row * cols)..((row + 1) * cols).min(mine.len()) {
+                    let conflict = base[off] != mine[off] && base[off] != theirs[off] && mine[off] != theirs[off];
+                    for (panel, bytes) in panels.iter().enumerate() {
+                        let pos = cell_pos(panel, off);
+                        let max = [pos[0] + glyph_width * 2.0, pos[1] + line_height];
+                        if conflict {
+                            draw_list.add_rect(pos, max, conflict_color).filled(true).build();
+                        } else if bytes[off] != base[off] {
+                            draw_list.add_rect(pos, max, changed_color).filled(true).build();
+                        }
+                        draw_list.add_text(pos, text_color, &self.format_hex(bytes[off] as usize, 2));
+                    }
+                }
+            }
+            ui.dummy([addr_width + panel_width * 3.0, rows as f32 * line_height]);
+
+            if ui.is_window_hovered() && ui.is_mouse_clicked(MouseButton::Left) && !self.raw.ReadOnly {
+                let [x, y] = ui.io().mouse_pos;
+                let row = ((y - origin[1]) / line_height) as usize;
+                let x = x - origin[0] - addr_width;
+                let col = ((x - 2.0 * panel_width) / (glyph_width * 3.0)) as usize;
+                let off = row * cols + col;
+                if y >= origin[1] && x >= 2.0 * panel_width && col < cols && off < mine.len() {
+                    take = Some((off, theirs[off]));
+                }
+            }
+        });
+        if let Some((off, d)) = take {
+            mine[off] = d;
+            self.state.last_write = Some(off);
+            self.state.last_edit_source = None;
+        }
+    }
+
+    // Name an offset. Returns the index of the bookmark.
+    pub fn add_bookmark(&mut self, offset: usize, name: String) -> usize {
+        self.state.bookmarks.push(Bookmark { offset, name });
+        self.state.bookmarks.len() - 1
+    }
+
+    pub fn remove_bookmark(&mut self, index: usize) {
+        self.state.bookmarks.remove(index);
+        self.state.relative_to_bookmark = match self.state.relative_to_bookmark {
+            Some(i) if i == index => None,
+            Some(i) if i > index => Some(i - 1),
+            relative => relative,
+        };
+    }
+
+    // Offsets and names, in the order they were added
+    pub fn bookmarks(&self) -> impl Iterator<Item = (usize, &str)> + '_ {
+        self.state.bookmarks.iter().map(|bookmark| (bookmark.offset, bookmark.name.as_str()))
+    }
+
+    // Bookmarks, sections, magic badges and markers at offsets in range, sorted by offset
+    pub fn items_in_range(&self, range: Range<usize>) -> Vec<NavItem<'_>> {
+        let mut items: Vec<NavItem> = self
+            .state
+            .bookmarks
+            .iter()
+            .map(|bookmark| NavItem::Bookmark { offset: bookmark.offset, name: &bookmark.name })
+            .chain(self.state.sections.iter().map(|(&offset, label)| NavItem::Section { offset, label }))
+            .chain(self.state.magic_badges.iter().map(|badge| NavItem::MagicBadge {
+                offset: badge.offset,
+                label: &badge.label,
+            }))
+            .chain(self.state.markers.iter().map(|&(offset, color)| NavItem::Marker { offset, color }))
+            .filter(|item| range.contains(&item.offset()))
+            .collect();
+        items.sort_by_key(|item| item.offset());
+        items
+    }
+
+    // Show addresses as name+0xNN relative to the bookmark at index, or absolute addresses when None.
+    // Offsets passed to handlers are unchanged.
+    pub fn relative_to_bookmark(&mut self, index: Option<usize>) {
+        assert!(index.map_or(true, |index| index < self.state.bookmarks.len()), "Bookmark index out of range");
+        self.state.relative_to_bookmark = index;
+    }
+
+    // Draw a thin line under the row containing addr, e.g. for cursors. There is one marker per color,
+    // so setting a marker moves the one of the same color and None removes it.
+    pub fn marker(&mut self, addr: Option<usize>, color: ImColor32) {
+        self.state.markers.retain(|&(_, c)| c != color);
+        if let Some(addr) = addr {
+            self.state.markers.push((addr, color));
+        }
+    }
+
+    // Scroll position from 0 (top) to 1 (bottom) as of the last draw
+    #[inline]
+    pub fn scroll_fraction(&self) -> f32 {
+        self.state.layout.scroll_fraction()
+    }
+
+    // Scroll to a position from 0 (top) to 1 (bottom) on the next draw
+    pub fn set_scroll_fraction(&mut self, fraction: f32) {
+        self.state.pending_scroll = Some(fraction.max(0.0).min(1.0) * self.state.layout.scroll_max_y);
+    }
+
+    // Size of the window created by draw_window as of the last draw, None when not drawing a window
+    #[inline]
+    pub fn window_size_actual(&self) -> Option<[f32; 2]> {
+        if self.state.window_name.is_some() {
+            self.state.window_size
+        } else {
+            None
+        }
+    }
+
+    // Cells the C++ editor drew as of the last draw, None before the first draw
+    #[inline]
+    pub fn visible_range(&self) -> Option<Range<usize>> {
+        self.state.layout.visible.map(|(first, last)| first..last)
+    }
+
+    // Whether the column count was below 1 and clamped to 1, when set through cols or through raw before a draw
+    #[inline]
+    pub fn cols_clamped(&self) -> bool {
+        self.state.cols_clamped
+    }
+
+    // raw is public, so Cols may have been set without going through the builder.
+    // The C++ editor divides by it, so clamp it the same way its options menu does.
+    fn clamp_cols(&mut self) {
+        if self.raw.Cols < 1 {
+            self.raw.Cols = 1;
+            self.state.cols_clamped = true;
+        }
+    }
+
+    // Number of rows that fit in the editor as of the last draw
+    #[inline]
+    pub fn visible_row_count(&self) -> usize {
+        self.state.layout.visible_rows()
+    }
+
+    // Sizes as of the last draw, which make room for address prefixes and labels and include the hex_ascii_gap.
+    // Before the first draw, the ones the C++ editor would compute on its own.
+    fn drawn_sizes(&self) -> sys::MemoryEditor_Sizes {
+        if self.state.layout.cols > 0 {
+            self.state.layout.sizes
+        } else {
+            layout::calc_sizes(&self.raw, self.state.mem_size, self.state.base_addr)
+        }
+    }
+
+    // Horizontal advance of a hex cell with the current font and style
+    pub fn cell_width(&self, _: &Ui) -> f32 {
+        self.drawn_sizes().HexCellWidth
+    }
+
+    // Width of the address column, including the spacing before the first hex cell
+    pub fn addr_column_width(&self, _: &Ui) -> f32 {
+        self.drawn_sizes().PosHexStart
+    }
+
+    // Offset of the ASCII column from the start of a row
+    pub fn ascii_column_offset(&self, _: &Ui) -> f32 {
+        self.drawn_sizes().PosAsciiStart
+    }
+
+    // Offset of the byte at a screen position as of the last draw, in either the hex or ASCII column
+    pub fn addr_at_pos(&self, pos: [f32; 2]) -> Option<usize> {
+        if self.state.layout.contains(pos) {
+            self.state.layout.addr_at(pos)
+        } else {
+            None
+        }
+    }
+
+    // Selected range of offsets, if any
+    pub fn highlight_range(&self) -> Option<Range<usize>> {
+        if self.raw.HighlightMin < self.raw.HighlightMax {
+            Some(self.raw.HighlightMin..self.raw.HighlightMax)
+        } else {
+            None
+        }
+    }
+
+    // Number of highlighted bytes, the same bounds as highlight_range
+    #[inline]
+    pub fn selection_len(&self) -> usize {
+        self.highlight_range().map_or(0, |range| range.len())
+    }
+
+    fn set_selection(&mut self, range: Range<usize>) {
+        if self.highlight_range().as_ref() == Some(&range) {
+            return;
+        }
+        self.raw.HighlightMin = range.start;
+        self.raw.HighlightMax = range.end;
+        if let Some(on_selection_change) = self.state.on_selection_change.as_mut() {
+            on_selection_change(range);
+        }
+    }
+
+    // Whether a cell is currently being edited
+    #[inline]
+    pub fn is_editing(&self) -> bool {
+        self.raw.DataEditingAddr != usize::MAX
+    }
+
+    // Read len bytes starting at offset through the read fn, stopping at mem_size
+    pub fn read_bytes(&self, data: &T, offset: usize, len: usize) -> Vec<u8> {
+        let end = offset.saturating_add(len).min(self.state.mem_size);
+        let mut read_fn = self.read_fn.borrow_mut();
+        let read_fn = read_fn.as_mut().expect("Read Fn must be set to read bytes");
+        let mut ctx = self.ctx.borrow_mut();
+        (offset.min(end)..end).map(|off| read_fn(data, &mut ctx, off)).collect()
+    }
+
+    // Text dump of the scan range as the editor draws it, one line per row, e.g. for golden tests.
+    // Doesn't need an imgui context. Spacing between mid columns is approximated with an extra space,
+    // and display fns and custom address labels are not applied.
+    pub fn render_to_string(&self, data: &T) -> String {
+        self.render_rows(data, self.scan_range())
+    }
+
+    // Write the rows visible as of the last draw, in the same format as render_to_string.
+    // Nothing is written if the editor hasn't been drawn yet.
+    pub fn export_visible(&self, data: &T, writer: &mut dyn Write) -> io::Result<()> {
+        match self.visible_range() {
+            Some(range) => writer.write_all(self.render_rows(data, range).as_bytes()),
+            None => Ok(()),
+        }
+    }
+
+    // Rows covering range, with the bytes outside of it left out
+    fn render_rows(&self, data: &T, range: Range<usize>) -> String {
+        let mut read_fn = self.read_fn.borrow_mut();
+        let read_fn = read_fn.as_mut().expect("Read Fn must be set to render memory");
+        let mut ctx = self.ctx.borrow_mut();
+        let cols = self.raw.Cols.max(1) as usize;
+        let mid_cols = self.raw.OptMidColsCount.max(0) as usize;
+        let digits = self.addr_digits(self.state.mem_size);
+
+        let mut out = String::new();
+        for row in (range.start / cols)..((range.end + cols - 1) / cols) {
+            let mut hex = String::new();
+            let mut ascii = String::new();
+            for col in 0..cols {
+                if mid_cols > 0 && col > 0 && col % mid_cols == 0 {
+                    hex.push(' ');
+                }
+                let addr = row * cols + col;
+                if addr < range.start {
+                    hex.push_str("   ");
+                    ascii.push(' ');
+                    continue;
+                }
+                if addr >= range.end {
+                    continue;
+                }
+                let d = read_fn(data, &mut ctx, addr);
+                let cell = match d {
+                    _ if !self.raw.OptShowHexII => self.format_hex(d as usize, 2),
+                    32..=127 => format!(".{}", d as char),
+                    0x00 => "  ".to_string(),
+                    0xFF if self.raw.OptGreyOutZeroes => "##".to_string(),
+                    _ => self.format_hex(d as usize, 2),
+                };
+                hex.push_str(&cell);
+                hex.push(' ');
+                ascii.push(match d {
+                    b'\t' if self.state.ascii_show_control => '\u{21E5}',
+                    b'\n' if self.state.ascii_show_control => '\u{21B5}',
+                    b'\r' if self.state.ascii_show_control => '\u{2190}',
+                    32..=127 => d as char,
+                    _ => '.',
+                });
+            }
+            let addr = self.format_hex(self.state.base_addr + row * cols, digits);
+            let mut line = format!("{}: ", self.state.addr_prefix.apply(addr));
+            if self.raw.OptShowAscii {
+                let hex_width = cols * 3 + if mid_cols > 0 { (cols - 1) / mid_cols } else { 0 };
+                line.push_str(&format!("{:1$} {2}", hex, hex_width, ascii));
+            } else {
+                line.push_str(&hex);
+            }
+            out.push_str(line.trim_end());
+            out.push('\n');
+        }
+        out
+    }
+
+    // FNV-1a hash of the scan range through the read fn, which is the same across runs and platforms
+    pub fn content_hash(&self, data: &T) -> u64 {
+        let mut read_fn = self.read_fn.borrow_mut();
+        let read_fn = read_fn.as_mut().expect("Read Fn must be set to hash memory");
+        let mut ctx = self.ctx.borrow_mut();
+        self.scan_range().fold(0xcbf2_9ce4_8422_2325, |hash, off| {
+            (hash ^ read_fn(data, &mut ctx, off) as u64).wrapping_mul(0x0000_0100_0000_01b3)
+        })
+    }
+
+    // Same as MemoryEditor::CalcSizes, without needing an imgui context
+    fn addr_digits(&self, mem_size: usize) -> usize {
+        let mut digits = self.raw.OptAddrDigitsCount.max(0) as usize;
+        if digits == 0 {
+            let mut n = (self.state.base_addr + mem_size).saturating_sub(1);
+            while n > 0 {
+                digits += 1;
+                n >>= 4;
+            }
+        }
+        digits
+    }
+
+    // Number of bytes that differ between data and baseline, compared through the read fn and the diff_with eq if set
+    pub fn diff_count(&self, data: &T, baseline: &T) -> usize {
+        let mut read_fn = self.read_fn.borrow_mut();
+        let read_fn = read_fn.as_mut().expect("Read Fn must be set to diff memory");
+        let mut diff_eq = self.state.diff_eq.borrow_mut();
+        let mut ctx = self.ctx.borrow_mut();
+        self.scan_range()
+            .filter(|&off| {
+                let (d, b) = (read_fn(data, &mut ctx, off), read_fn(baseline, &mut ctx, off));
+                match diff_eq.as_mut() {
+                    Some(eq) => !eq(off, d, b),
+                    None => d != b,
+                }
+            })
+            .count()
+    }
+
+    // Offsets visited by whole memory scans
+    fn scan_range(&self) -> Range<usize> {
+        0..self.state.scan_limit.map_or(self.state.mem_size, |limit| limit.min(self.state.mem_size))
+    }
+
+    // Draw the memory editor with read and write functions set
+    pub fn draw(&mut self, ui: &Ui, user_data: &mut T) {
+        assert!(
+            self.read_fn.get_mut().is_some() || self.state.mem_size == 0,
+            "Read Fn must be set if mem size > 0"
+        );
+        assert!(
+            self.write_fn.is_some() || self.write_result_fn.is_some() || self.raw.ReadOnly || self.state.mem_size == 0,
+            "Write Fn must be set if not read only and mem size > 0"
+        );
+        let mut read_fn = self.read_fn.get_mut().take();
+        let mut write_fn = self.write_fn.take();
+        let mut highlight_fn = self.highlight_fn.take();
+
+        self.draw_user(
+            ui,
+            read_fn.as_deref_mut().map(|f| f as &mut dyn FnMut(&T, &mut C, usize) -> u8),
+            write_fn.as_deref_mut().map(|f| f as &mut dyn FnMut(&mut T, &mut C, usize, u8)),
+            highlight_fn.as_deref_mut().map(|f| f as &mut dyn FnMut(&T, &mut C, usize) -> bool),
+            user_data,
+        );
+
+        *self.read_fn.get_mut() = read_fn;
+        self.write_fn = write_fn;
+        self.highlight_fn = highlight_fn;
+    }
+
+    // Draw the memory editor with handlers borrowed for this frame only, instead of the ones set on the editor.
+    // This is the safe alternative to building mem_data for draw_raw by hand.
+    pub fn draw_with_handlers<'b>(
+        &mut self,
+        ui: &Ui,
+        read: &'b mut dyn FnMut(&T, usize) -> u8,
+        write: Option<&'b mut dyn FnMut(&mut T, usize, u8)>,
+        highlight: Option<&'b mut dyn FnMut(&T, usize) -> bool>,
+        user_data: &'b mut T,
+    ) {
+        assert!(
+            write.is_some() || self.write_result_fn.is_some() || self.raw.ReadOnly || self.state.mem_size == 0,
+            "Write handler must be given if not read only and mem size > 0"
+        );
+        // These don't take the context
+        let mut read = |data: &T, _: &mut C, off: usize| read(data, off);
+        let mut write = write.map(|write| move |data: &mut T, _: &mut C, off: usize, d: u8| write(data, off, d));
+        let mut highlight = highlight.map(|highlight| move |data: &T, _: &mut C, off: usize| highlight(data, off));
+        self.draw_user(
+            ui,
+            Some(&mut read as &mut dyn FnMut(&T, &mut C, usize) -> u8),
+            write.as_mut().map(|f| f as &mut dyn FnMut(&mut T, &mut C, usize, u8)),
+            highlight.as_mut().map(|f| f as &mut dyn FnMut(&T, &mut C, usize) -> bool),
+            user_data,
+        );
+    }
+
+    fn draw_user<'b>(
+        &mut self,
+        ui: &'b Ui<'b>,
+        read_fn: Option<&'b mut dyn FnMut(&T, &mut C, usize) -> u8>,
+        write_fn: Option<&'b mut dyn FnMut(&mut T, &mut C, usize, u8)>,
+        highlight_fn: Option<&'b mut dyn FnMut(&T, &mut C, usize) -> bool>,
+        user_data: &'b mut T,
+    ) {
+        let mut display_fn = self.display_fn.take();
+        let mut flag_fn = self.flag_fn.take();
+        let mut write_result_fn = self.write_result_fn.take();
+        // Casts shorten the handlers' lifetimes to match display_fn's
+        let mut data = MemData {
+            ctx: self.ctx.as_ptr(),
+            editor: self,
+            ui,
+            read_fn: read_fn.map(|f| f as &mut dyn FnMut(&T, &mut C, usize) -> u8),
+            write_fn: write_fn.map(|f| f as &mut dyn FnMut(&mut T, &mut C, usize, u8)),
+            write_result_fn: write_result_fn
+                .as_deref_mut()
+                .map(|f| f as &mut dyn FnMut(&mut T, &mut C, usize, u8) -> Result<(), String>),
+            highlight_fn: highlight_fn.map(|f| f as &mut dyn FnMut(&T, &mut C, usize) -> bool),
+            display_fn: display_fn.as_deref_mut().map(|f| f as &mut dyn FnMut(&T, &mut C, usize, u8) -> u8),
+            flag_fn: flag_fn
+                .as_deref_mut()
+                .map(|f| f as &mut dyn FnMut(&T, &mut C, usize) -> Option<ImColor32>),
+            source: Source::User(user_data),
+            reads: 0,
+        };
+        self.draw_frame(&mut data);
+        self.state.last_reads = data.reads;
+        self.display_fn = display_fn;
+        self.flag_fn = flag_fn;
+        self.write_result_fn = write_result_fn;
+    }
+
+    fn draw_frame(&mut self, mem: &mut MemData<'a, '_, T, C>) {
+        let ui = mem.ui;
+        self.clamp_cols();
+        self.raw.ReadFn = Some(read_wrapper::<T, C>);
+        self.raw.WriteFn = Some(write_wrapper::<T, C>);
+        self.raw.HighlightFn = Some(highlight_wrapper::<T, C>);
+        // Make the C++ editor leave room for custom address labels
+        if self.state.lock_addr_digits && self.raw.OptAddrDigitsCount == 0 {
+            let sizes = layout::calc_sizes(&self.raw, self.state.mem_size, self.state.base_addr);
+            self.raw.OptAddrDigitsCount = sizes.AddrDigitsCount;
+            self.state.addr_digits_locked = true;
+        }
+        let addr_digits_count = self.raw.OptAddrDigitsCount;
+        let show_hex_ii = self.raw.OptShowHexII;
+        // HexII draws nothing for zero, which is returned for cells whose value is drawn here
+        if self.state.value_display != ValueDisplay::Hex || self.state.missing_fn.is_some() {
+            self.raw.OptShowHexII = true;
+        }
+        let addr_digits = layout::calc_sizes(&self.raw, self.state.mem_size, self.state.base_addr).AddrDigitsCount;
+        if let Some(label_len) = self.address_label_len(addr_digits as usize) {
+            self.raw.OptAddrDigitsCount = addr_digits.max(label_len as i32);
+        }
+        let ascii_shift = self.state.hex_ascii_gap.map_or(0.0, |gap| {
+            (gap - layout::calc_sizes(&self.raw, self.state.mem_size, self.state.base_addr).GlyphWidth).max(0.0)
+        });
+        self.state.layout.begin(&self.raw, self.state.mem_size, self.state.base_addr, ascii_shift);
+        self.state.layout.hex_ii_forced = self.raw.OptShowHexII && !show_hex_ii;
+        self.state.frame_deadline = self.state.frame_budget.map(|budget| Instant::now() + budget);
+        self.state.layout.addr_digits = addr_digits as usize;
+        // Everything drawn past the ASCII column ends where the trailing space starts
+        let sizes = &self.state.layout.sizes;
+        let past_ascii = self.trailing_offset() - (sizes.PosAsciiEnd + sizes.GlyphWidth);
+        self.state.layout.sizes.WindowWidth += past_ascii + self.sections_width(ui);
+        if self.state.overview_bar {
+            self.state.layout.sizes.WindowWidth += self.state.layout.sizes.GlyphWidth * OVERVIEW_GLYPHS;
+        }
+
+        // Same as MemoryEditor::DrawWindow, but leaves room to draw around the contents
+        if let Some(title) = self.state.window_name {
+            let mut open = true;
+            // Eager contents are as high as all rows, so the window scrolls instead of the C++ editor
+            let (flags, width) = match self.state.render_mode {
+                RenderMode::Eager => {
+                    (WindowFlags::empty(), self.state.layout.sizes.WindowWidth + ui.clone_style().scrollbar_size)
+                }
+                RenderMode::Clipped => (WindowFlags::NO_SCROLLBAR, self.state.layout.sizes.WindowWidth),
+            };
+            let mut window = Window::new(title)
+                .opened(&mut open)
+                .flags(flags)
+                .size([width, width * 0.60], Condition::FirstUseEver)
+                .size_constraints([0.0, 0.0], [width, f32::MAX]);
+            if let Some(height) = self.state.resize_window.take() {
+                window = window.size([width, height], Condition::Always);
+            }
+            if !self.state.was_open {
+                if let Some(on_open) = self.state.on_open.as_mut() {
+                    on_open();
+                }
+            }
+            if let Some(token) = window.begin(ui) {
+                let disabled_token = ui.begin_disabled(self.state.disabled);
+                self.draw_contents(mem);
+                disabled_token.end();
+                if self.raw.ContentsWidthChanged {
+                    self.state.resize_window = Some(ui.window_size()[1]);
+                }
+                self.state.window_size = Some(ui.window_size());
+                token.end(ui);
+            }
+            self.raw.Open = open;
+            self.state.was_open = open;
+        } else {
+            let disabled_token = ui.begin_disabled(self.state.disabled);
+            self.draw_contents(mem);
+            disabled_token.end();
+        }
+        self.raw.OptAddrDigitsCount = addr_digits_count;
+        self.raw.OptShowHexII = show_hex_ii;
+        self.follow_last_write();
+        if self.state.disabled {
+            return;
+        }
+
+        // Remember which column the byte to edit was clicked in, for last_edit_source.
+        // The C++ editor handles clicks where it drew the ASCII column, not where it was shifted to.
+        let layout = &self.state.layout;
+        if let Some(addr) = layout.hovered_addr.filter(|_| layout.hovered && ui.is_mouse_clicked(MouseButton::Left)) {
+            let [x, _] = ui.io().mouse_pos;
+            let ascii_shift = layout.ascii_shift;
+            let ascii_start = layout.origin[0] + layout.sizes.PosAsciiStart - ascii_shift;
+            if self.raw.OptShowAscii && x >= ascii_start {
+                self.state.edit_source = EditSource::Ascii;
+                if ascii_shift > 0.0 {
+                    self.raw.DataEditingAddr = addr;
+                    self.raw.DataPreviewAddr = addr;
+                    self.raw.DataEditingTakeFocus = true;
+                }
+            } else {
+                self.state.edit_source = EditSource::Hex;
+            }
+        }
+
+        // The C++ editor moves on to the next byte after an edit, which may not be aligned
+        if self.is_editing() && self.raw.DataEditingAddr % self.state.edit_alignment != 0 {
+            self.raw.DataEditingAddr = usize::MAX;
+            self.raw.DataEditingTakeFocus = false;
+        }
+        self.handle_selected_click(ui);
+        self.handle_drag_select(ui);
+        self.handle_delete(mem);
+        self.handle_copy(mem);
+        self.handle_keys(mem);
+        if self.state.hover_preview {
+            self.draw_hover_preview(mem);
+        }
+        self.handle_paging_keys(ui);
+    }
+
+    fn handle_delete(&mut self, mem: &mut MemData<'a, '_, T, C>) {
+        let ui = mem.ui;
+        if !self.state.layout.focused || self.is_editing() {
+            return;
+        }
+        let range = match self.highlight_range() {
+            Some(range) => range.start..range.end.min(self.state.mem_size),
+            None => return,
+        };
+        if !ui.is_key_pressed(Key::Delete) && !ui.is_key_pressed(Key::Backspace) {
+            return;
+        }
+        if let (Some(on_delete), Source::User(user_data)) = (self.on_delete.as_mut(), &mut mem.source) {
+            on_delete(user_data, unsafe { &mut *mem.ctx }, range);
+        } else if !self.raw.ReadOnly {
+            for off in range {
+                self.write_cell(mem, off, 0, None);
+            }
+        }
+    }
+
+    // The C++ editor has already started editing the clicked cell, so undo that if needed
+    fn handle_selected_click(&mut self, ui: &Ui) {
+        if self.state.click_behavior == ClickBehavior::Edit
+            || !self.state.layout.hovered
+            || !ui.is_mouse_clicked(MouseButton::Left)
+        {
+            return;
+        }
+        let addr = match (self.state.layout.hovered_addr, self.highlight_range()) {
+            (Some(addr), Some(range)) if range.contains(&addr) => addr,
+            _ => return,
+        };
+        self.raw.DataEditingAddr = usize::MAX;
+        self.raw.DataEditingTakeFocus = false;
+        match self.state.click_behavior {
+            ClickBehavior::ToggleSelect => self.set_selection(0..0),
+            ClickBehavior::StartRangeSelect => self.set_selection(addr..addr + 1),
+            ClickBehavior::Edit => unreachable!(),
+        }
+    }
+
+    fn handle_keys(&mut self, mem: &mut MemData<'a, '_, T, C>) {
+        let ui = mem.ui;
+        if !self.state.layout.focused || self.is_editing() {
+            return;
+        }
+        let selection = self.highlight_range();
+        if let (Some(on_key), Source::User(user_data)) = (self.on_key.as_mut(), &mut mem.source) {
+            let io = ui.io();
+            let mods = KeyMods { ctrl: io.key_ctrl, shift: io.key_shift, alt: io.key_alt, super_: io.key_super };
+            let ctx = unsafe { &mut *mem.ctx };
+            for &key in Key::VARIANTS.iter().filter(|&&key| ui.is_key_pressed(key)) {
+                on_key(user_data, ctx, key, &mods, selection.clone());
+            }
+        }
+    }
+
+    fn handle_copy(&mut self, mem: &mut MemData<'a, '_, T, C>) {
+        let ui = mem.ui;
+        if !self.state.layout.focused || self.is_editing() || !ui.io().key_ctrl || !ui.is_key_pressed(Key::C) {
+            return;
+        }
+        let range = match self.highlight_range() {
+            Some(range) => range.start..range.end.min(self.state.mem_size),
+            None => return,
+        };
+        let bytes: Vec<u8> = range.map(|off| mem.read(off)).collect();
+        let text = match self.state.copy_format_fn.as_mut() {
+            Some(copy_format_fn) => copy_format_fn(&bytes),
+            None => bytes
+                .iter()
+                .map(|&d| self.format_hex(d as usize, 2))
+                .collect::<Vec<_>>()
+                .join(" "),
+        };
+        ui.set_clipboard_text(&ImString::new(text));
+    }
+
+    fn handle_drag_select(&mut self, ui: &Ui) {
+        if !self.state.drag_select {
+            return;
+        }
+        if ui.is_mouse_clicked(MouseButton::Left) && self.state.layout.hovered {
+            self.state.drag_anchor = self.state.layout.hovered_addr;
+            self.state.dragging = false;
+        }
+        let anchor = match self.state.drag_anchor {
+            Some(anchor) if ui.is_mouse_down(MouseButton::Left) => anchor,
+            _ => {
+                self.state.drag_anchor = None;
+                return;
+            }
+        };
+
+        let layout = &self.state.layout;
+        let [x, y] = ui.io().mouse_pos;
+        let top = layout.window_pos[1];
+        let bottom = top + layout.window_size[1];
+        let line_height = layout.sizes.LineHeight;
+        let addr = layout.addr_at([x, y.max(top).min(bottom - 1.0)]);
+        if y < top {
+            self.state.pending_scroll = Some((layout.scroll_y - line_height).max(0.0));
+        } else if y >= bottom {
+            self.state.pending_scroll = Some((layout.scroll_y + line_height).min(layout.scroll_max_y));
+        }
+
+        // A click without dragging is left to the C++ editor to start editing
+        self.state.dragging |= addr.map_or(false, |addr| addr != anchor);
+        if let Some(addr) = addr.filter(|_| self.state.dragging) {
+            self.raw.DataEditingAddr = usize::MAX;
+            self.set_selection(anchor.min(addr)..anchor.max(addr) + 1);
+        }
+    }
+
+    fn draw_contents(&mut self, mem: &mut MemData<'a, '_, T, C>) {
+        let ui = mem.ui;
+        self.draw_magic_badges(mem);
+
+        let mut colors = Vec::new();
+        if let Some(color) = self.state.background_color {
+            // The scrolling region is a child window
+            colors.push((StyleColor::ChildBg, color.to_rgba_f32s()));
+        }
+        if let Some(rows) = self.sized_rows() {
+            // Same footer as MemoryEditor::DrawContents, except for the preview
+            let mut height = rows as f32 * self.state.layout.sizes.LineHeight;
+            if self.raw.OptShowOptions {
+                height += ui.clone_style().item_spacing[1] + ui.frame_height_with_spacing();
+            }
+            if self.raw.OptShowDataPreview {
+                height += self.preview_footer_height(ui);
+            }
+            ChildWindow::new(im_str!("##fixed_rows"))
+                .size([0.0, height])
+                .flags(WindowFlags::NO_SCROLLBAR | WindowFlags::NO_SCROLL_WITH_MOUSE)
+                .build(ui, || self.draw_body(mem, &colors));
+        } else {
+            self.draw_body(mem, &colors);
+        }
+
+        if self.state.debug_overlay {
+            self.draw_debug_overlay(ui);
+        }
+        if self.state.overview_bar {
+            self.draw_overview_bar(ui);
+        }
+    }
+
+    // Rows the contents are sized to, instead of filling the window
+    fn sized_rows(&self) -> Option<u32> {
+        match self.state.render_mode {
+            // The clipper lays out the rows that fit in the scrolling region, so fit all of them in it
+            RenderMode::Eager => {
+                let cols = self.raw.Cols.max(1) as usize;
+                Some(((self.state.mem_size + cols - 1) / cols).max(1) as u32)
+            }
+            RenderMode::Clipped => self.state.fixed_rows,
+        }
+    }
+
+    fn preview_footer_height(&self, ui: &Ui) -> f32 {
+        let mut height = ui.clone_style().item_spacing[1];
+        if self.state.collapsible_footer {
+            height += ui.frame_height_with_spacing();
+        }
+        if !self.state.collapsible_footer || self.state.footer_open {
+            height += ui.frame_height_with_spacing() + ui.text_line_height_with_spacing() * 3.0;
+        }
+        height
+    }
+
+    // Scrolling region and footer
+    fn draw_body(&mut self, mem: &mut MemData<'a, '_, T, C>, colors: &[(StyleColor, [f32; 4])]) {
+        let ui = mem.ui;
+        // The C++ editor only leaves room for its own footer, so fit it in a child above ours
+        if self.raw.OptShowDataPreview && self.custom_preview() {
+            self.raw.OptShowDataPreview = false;
+            let footer_height = self.preview_footer_height(ui);
+            ChildWindow::new(im_str!("##contents"))
+                .size([0.0, -footer_height])
+                .flags(WindowFlags::NO_SCROLLBAR | WindowFlags::NO_SCROLL_WITH_MOUSE)
+                .build(ui, || {
+                    let color_token = ui.push_style_colors(colors);
+                    self.draw_sys_contents(mem);
+                    color_token.pop(ui);
+                });
+            self.raw.OptShowDataPreview = true;
+            ui.separator();
+            if self.state.collapsible_footer {
+                // Toggling resizes the contents on the next frame, since their height is needed before the header
+                self.state.footer_open = CollapsingHeader::new(im_str!("Preview##footer")).default_open(true).build(ui);
+                if self.state.footer_open {
+                    self.draw_preview(mem);
+                }
+            } else {
+                self.draw_preview(mem);
+            }
+        } else {
+            let color_token = ui.push_style_colors(colors);
+            self.draw_sys_contents(mem);
+            color_token.pop(ui);
+        }
+    }
+
+    fn draw_sys_contents(&mut self, mem: &mut MemData<'a, '_, T, C>) {
+        let ui = mem.ui;
+        // Done by MemoryEditor::DrawWindow, from the window the options popup is in
+        if self.state.window_name.is_some()
+            && ui.is_window_hovered_with_flags(WindowHoveredFlags::ROOT_AND_CHILD_WINDOWS)
+            && ui.is_mouse_released(MouseButton::Right)
+        {
+            ui.open_popup(im_str!("context"));
+        }
+        // The scrolling region is created by the C++ editor, so its scrollbar can only be hidden through the style
+        let style_token = if self.state.external_scroll || self.sized_rows().is_some() {
+            Some(ui.push_style_var(StyleVar::ScrollbarSize(0.0)))
+        } else {
+            None
+        };
+        let (mem_size, base_addr) = (self.state.mem_size, self.state.base_addr);
+        // The callbacks reach the editor, including raw, through mem.editor while the C++ editor is drawing.
+        // So both only go through this pointer until DrawContents returns, and self isn't used meanwhile.
+        let editor: *mut Self = self;
+        mem.editor = editor;
+        unsafe {
+            sys::Editor_DrawContents(
+                ptr::addr_of_mut!((*editor).raw),
+                mem as *mut MemData<T, C> as *mut c_void,
+                mem_size,
+                base_addr,
+            );
+        }
+        self.pop_flat_colors();
+        if let Some(style_token) = style_token {
+            style_token.pop(ui);
+        }
+        if let Some(color) = self.state.cursor_color {
+            self.draw_cursor(color);
+        }
+    }
+
+    // Drawn after the C++ editor is done, since the input of the edited cell covers anything drawn before it
+    fn draw_cursor(&self, color: ImColor32) {
+        let layout = &self.state.layout;
+        let addr = self.raw.DataEditingAddr;
+        let (draw_list, (first, last)) = match (layout.draw_list, layout.visible) {
+            (Some(draw_list), Some(visible)) => (draw_list, visible),
+            _ => return,
+        };
+        if addr < first || addr >= last {
+            return;
+        }
+        let min = layout.cell_pos(addr);
+        let max = [min[0] + layout.sizes.GlyphWidth * 2.0, min[1] + layout.sizes.LineHeight];
+        unsafe {
+            imgui::sys::ImDrawList_AddRectFilled(
+                draw_list.as_ptr(),
+                imgui::sys::ImVec2::new(min[0], min[1]),
+                imgui::sys::ImVec2::new(max[0], max[1]),
+                color.to_bits(),
+                0.0,
+                0,
+            );
+        }
+    }
+
+    // Whether the data preview is drawn here instead of by the C++ editor
+    fn custom_preview(&self) -> bool {
+        self.state.allowed_preview_types.is_some()
+            || self.state.preview_signed_unsigned
+            || self.state.clamp_preview_to_selection
+            || self.state.collapsible_footer
+    }
+
+    fn preview_types(&self) -> &[PreviewDataType] {
+        self.state.allowed_preview_types.as_deref().unwrap_or(&PreviewDataType::ALL)
+    }
+
+    // Same as MemoryEditor::DrawPreviewLine
+    fn draw_preview(&mut self, mem: &mut MemData<'a, '_, T, C>) {
+        let ui = mem.ui;
+        let style = ui.clone_style();
+        let glyph_width = self.state.layout.sizes.GlyphWidth;
+        let current = PreviewDataType::from_raw(self.raw.PreviewDataType).unwrap_or(self.preview_types()[0]);
+
+        ui.align_text_to_frame_padding();
+        ui.text("Preview as:");
+        ui.same_line(0.0);
+        ui.set_next_item_width(glyph_width * 10.0 + style.frame_padding[0] * 2.0 + style.item_inner_spacing[0]);
+        if let Some(token) = ComboBox::new(im_str!("##combo_type"))
+            .preview_value(&ImString::new(current.name()))
+            .flags(ComboBoxFlags::HEIGHT_LARGEST)
+            .begin(ui)
+        {
+            let selected = self
+                .preview_types()
+                .iter()
+                .copied()
+                .filter(|&ty| Selectable::new(&ImString::new(ty.name())).selected(ty == current).build(ui))
+                .last();
+            if let Some(ty) = selected {
+                self.raw.PreviewDataType = ty as sys::ImGuiDataType;
+            }
+            token.end(ui);
+        }
+        ui.same_line(0.0);
+        ui.set_next_item_width(glyph_width * 6.0 + style.frame_padding[0] * 2.0 + style.item_inner_spacing[0]);
+        let endianess = if self.raw.PreviewEndianess == 0 { im_str!("LE") } else { im_str!("BE") };
+        if let Some(token) = ComboBox::new(im_str!("##combo_endianess")).preview_value(endianess).begin(ui) {
+            for (i, name) in [im_str!("LE"), im_str!("BE")].iter().enumerate() {
+                if Selectable::new(name).selected(self.raw.PreviewEndianess == i as i32).build(ui) {
+                    self.raw.PreviewEndianess = i as i32;
+                }
+            }
+            token.end(ui);
+        }
+
+        let too_short = self.state.clamp_preview_to_selection
+            && self.highlight_range().map_or(false, |range| {
+                let addr = self.raw.DataPreviewAddr;
+                range.contains(&addr) && range.end - addr < current.size()
+            });
+        let preview = if too_short { None } else { self.preview_value(mem, current) };
+        let missing = if too_short { "selection too short" } else { "N/A" };
+        let upper_case = self.raw.OptUpperCaseHex;
+        let signed_unsigned = self.state.preview_signed_unsigned;
+        let x = glyph_width * 6.0;
+        for (label, value) in [
+            ("Dec", preview.map(|p| if signed_unsigned { p.dec_signed_unsigned() } else { p.dec() })),
+            ("Hex", preview.map(|p| p.hex(upper_case))),
+            ("Bin", preview.map(|p| p.bin())),
+        ]
+        .iter()
+        {
+            ui.text(label);
+            ui.same_line(x);
+            ui.text(value.as_deref().unwrap_or(missing));
+        }
+    }
+
+    fn draw_hover_preview(&self, mem: &mut MemData<'a, '_, T, C>) {
+        let addr = match self.state.layout.hovered_addr {
+            Some(addr) if self.state.layout.hovered && !self.is_editing() => addr,
+            _ => return,
+        };
+        let previews: Vec<Preview> = PreviewDataType::ALL
+            .iter()
+            .filter_map(|&ty| self.preview_value_at(mem, addr, ty))
+            .collect();
+        let ui = mem.ui;
+        ui.tooltip(|| {
+            ui.text(self.format_addr(addr));
+            ui.separator();
+            let x = self.state.layout.sizes.GlyphWidth * 8.0;
+            for preview in &previews {
+                ui.text(preview.ty.name());
+                ui.same_line(x);
+                ui.text(preview.dec());
+            }
+            if let Some(byte) = previews.iter().find(|preview| preview.ty == PreviewDataType::Uint8) {
+                ui.text("Bin");
+                ui.same_line(x);
+                ui.text(byte.bin());
+            }
+        });
+    }
+
+    // Value at DataPreviewAddr, zero padded if the memory ends early
+    fn preview_value(&self, mem: &mut MemData<'a, '_, T, C>, ty: PreviewDataType) -> Option<Preview> {
+        self.preview_value_at(mem, self.raw.DataPreviewAddr, ty)
+    }
+
+    fn preview_value_at(&self, mem: &mut MemData<'a, '_, T, C>, addr: usize, ty: PreviewDataType) -> Option<Preview> {
+        if addr == usize::MAX || addr >= self.state.mem_size {
+            return None;
+        }
+        let mut bytes = [0; 8];
+        let end = (addr + ty.size()).min(self.state.mem_size);
+        for (i, off) in (addr..end).enumerate() {
+            bytes[i] = mem.display(off);
+        }
+        Some(Preview::decode(ty, &bytes[..ty.size()], self.raw.PreviewEndianess != 0))
+    }
+
+    fn draw_debug_overlay(&self, ui: &Ui) {
+        let layout = &self.state.layout;
+        let draw_list = match layout.region_draw_list() {
+            Some(draw_list) => draw_list,
+            None => return,
+        };
+        let text = format!(
+            "{:.0} fps\n{} reads\n{} rows\n{:.0}% scrolled",
+            ui.io().framerate,
+            self.state.last_reads,
+            self.visible_row_count(),
+            layout.scroll_fraction() * 100.0
+        );
+        let size = ui.calc_text_size(&ImString::new(text.as_str()), false, -1.0);
+        let padding = layout.sizes.GlyphWidth;
+        let bottom = layout.window_pos[1] + layout.window_size[1];
+        let min = [layout.window_pos[0] + padding, bottom - size[1] - padding * 3.0];
+        let max = [min[0] + size[0] + padding * 2.0, min[1] + size[1] + padding * 2.0];
+
+        draw_list.rect(min, max, ui.style_color(StyleColor::PopupBg).into());
+        draw_list.text([min[0] + padding, min[1] + padding], ui.style_color(StyleColor::Text).into(), &text);
+    }
+
+    fn draw_overview_bar(&mut self, ui: &Ui) {
+        let layout = &self.state.layout;
+        let (draw_list, (first, last)) = match (layout.region_draw_list(), layout.visible) {
+            (Some(draw_list), Some(visible)) if self.state.mem_size > 0 => (draw_list, visible),
+            _ => return,
+        };
+        let scrollbar = if layout.scroll_max_y > 0.0 { ui.clone_style().scrollbar_size } else { 0.0 };
+        let max = [layout.window_pos[0] + layout.window_size[0] - scrollbar, layout.window_pos[1] + layout.window_size[1]];
+        let min = [max[0] - layout.sizes.GlyphWidth * OVERVIEW_GLYPHS, layout.window_pos[1]];
+        let height = max[1] - min[1];
+        let y_of = |off: usize| min[1] + height * off as f32 / self.state.mem_size as f32;
+
+        draw_list.rect(min, max, ui.style_color(StyleColor::ScrollbarBg).into());
+        let thumb_max = [max[0], y_of(last).max(y_of(first) + 2.0)];
+        draw_list.rect([min[0], y_of(first)], thumb_max, ui.style_color(StyleColor::ScrollbarGrab).into());
+        let tick_color = ui.style_color(StyleColor::PlotHistogram).into();
+        let ticks = self
+            .items_in_range(0..self.state.mem_size)
+            .into_iter()
+            .filter(|item| matches!(item, NavItem::Bookmark { .. } | NavItem::Section { .. }));
+        for item in ticks {
+            let y = y_of(item.offset());
+            draw_list.line([min[0], y], [max[0], y], tick_color);
+        }
+        drop(draw_list);
+
+        let [x, y] = ui.io().mouse_pos;
+        if layout.hovered && ui.is_mouse_clicked(MouseButton::Left) && x >= min[0] && x < max[0] && y >= min[1] && y < max[1] {
+            let off = (((y - min[1]) / height * self.state.mem_size as f32) as usize).min(self.state.mem_size - 1);
+            self.goto_addr(off);
+        }
+    }
+
+    fn draw_magic_badges(&self, mem: &mut MemData<'a, '_, T, C>) {
+        let ui = mem.ui;
+        let color = ui.style_color(StyleColor::CheckMark);
+        let mut first = true;
+        for badge in &self.state.magic_badges {
+            if !badge.matches(self.state.mem_size, |off| mem.read(off)) {
+                continue;
+            }
+            if !first {
+                ui.same_line(0.0);
+            }
+            ui.text_colored(color, &badge.label);
+            first = false;
+        }
+    }
+
+    // Called by the C++ editor for every visible cell before it is drawn
+    fn visit_cell(&mut self, mem: &mut MemData<'a, '_, T, C>, off: usize) -> bool {
+        if self.state.layout.is_lookahead(mem.ui, off) {
+            return !self.state.layout.truncated && self.is_highlighted(mem, off);
+        }
+        self.pop_flat_colors();
+        let expects_read = self.raw.DataEditingAddr != off || self.raw.DataEditingTakeFocus;
+        if self.state.layout.visit(mem.ui, off, expects_read) {
+            if let Some(scroll_y) = self.state.pending_scroll.take() {
+                mem.ui.set_scroll_y(scroll_y);
+            }
+            // The scrolling region is created by the C++ editor, so its flags can't be changed
+            if self.state.lock_horizontal_scroll {
+                mem.ui.set_scroll_x(0.0);
+            }
+        }
+        let mut first_truncated = false;
+        let out_of_time = self.state.frame_deadline.map_or(false, |deadline| Instant::now() >= deadline);
+        if !self.state.layout.truncated && out_of_time {
+            self.state.layout.truncated = true;
+            first_truncated = true;
+            // Blank cells are drawn for zeroes in HexII, which is read for every cell. Restored after drawing.
+            self.raw.OptShowHexII = true;
+        }
+        if self.state.layout.truncated {
+            if first_truncated || off % self.state.layout.cols == 0 {
+                let pos = self.state.layout.cell_pos(off);
+                mem.ui.get_window_draw_list().add_text(pos, mem.ui.style_color(StyleColor::TextDisabled), "\u{2026}");
+            }
+            return false;
+        }
+        if let Some(color) = self.state.crosshair {
+            self.draw_crosshair(mem.ui, off, color);
+        }
+        if self.state.edit_alignment > 1 && off % self.state.edit_alignment == 0 && !self.raw.ReadOnly {
+            let [x, y] = self.state.layout.cell_pos(off);
+            let color = mem.ui.style_color(StyleColor::Separator);
+            let bottom = y + self.state.layout.sizes.LineHeight;
+            mem.ui.get_window_draw_list().add_line([x - 1.0, y], [x - 1.0, bottom], color).build();
+        }
+        if let Some(threshold) = self.state.outlier_threshold {
+            if off % self.state.layout.cols == 0 {
+                let end = (off + self.state.layout.cols).min(self.state.mem_size);
+                let sum: u32 = (off..end).map(|off| mem.read(off) as u32).sum();
+                self.state.row_mean = sum as f32 / (end - off) as f32;
+            }
+            if (mem.read(off) as f32 - self.state.row_mean).abs() > threshold as f32 {
+                let mut color = mem.ui.style_color(StyleColor::PlotHistogram);
+                color[3] *= 0.35;
+                self.fill_cell(mem.ui, off, color.into());
+            }
+        }
+        if !self.state.mask_rules.is_empty() {
+            let d = mem.read(off);
+            if let Some(rule) = self.state.mask_rules.iter().find(|rule| d & rule.mask == rule.value) {
+                self.fill_cell(mem.ui, off, rule.color);
+            }
+        }
+        if off % self.state.layout.cols == 0 {
+            self.draw_address(mem.ui, off);
+            if self.state.hex_ascii_divider && self.raw.OptShowAscii {
+                self.draw_divider(mem.ui, off);
+            }
+            self.draw_sections(mem.ui, off);
+        }
+        let row_end = off % self.state.layout.cols == self.state.layout.cols - 1 || off + 1 == self.state.mem_size;
+        if self.state.row_sparkline && row_end {
+            self.draw_row_sparkline(mem, off);
+        }
+        if !self.state.extra_columns.is_empty() && row_end {
+            self.draw_extra_columns(mem, off);
+        }
+        if row_end && !expects_read {
+            self.draw_markers(mem.ui, off);
+        }
+        // Without an ASCII column, nothing is called back after the last cell of the last row before the region ends
+        if self.state.flat_cells && off == self.raw.DataEditingAddr && (self.raw.OptShowAscii || !row_end) {
+            self.push_flat_colors();
+        }
+        self.is_highlighted(mem, off)
+    }
+
+    // The input the C++ editor draws over the cell being edited is the only tinted item in the scrolling region.
+    // So the flat colors are only pushed while it is drawn, leaving the tints of the footer below alone.
+    fn push_flat_colors(&mut self) {
+        const TRANSPARENT: imgui::sys::ImVec4 = imgui::sys::ImVec4 { x: 0.0, y: 0.0, z: 0.0, w: 0.0 };
+        let colors =
+            [StyleColor::ButtonHovered, StyleColor::ButtonActive, StyleColor::FrameBgHovered, StyleColor::FrameBgActive];
+        for &color in colors.iter() {
+            unsafe { imgui::sys::igPushStyleColor_Vec4(color as i32, TRANSPARENT) }
+        }
+        self.state.flat_pushed = true;
+    }
+
+    fn pop_flat_colors(&mut self) {
+        if self.state.flat_pushed {
+            unsafe { imgui::sys::igPopStyleColor(4) }
+            self.state.flat_pushed = false;
+        }
+    }
+
+    // Highlighted by the highlight fn or by differing from the diff baseline
+    fn is_highlighted(&mut self, mem: &mut MemData<'a, '_, T, C>, off: usize) -> bool {
+        let changed = match (self.state.diff_baseline, self.state.diff_eq.get_mut().as_mut()) {
+            (Some(baseline), Some(eq)) => !eq(off, mem.read(off), mem.read_from(baseline, off)),
+            _ => false,
+        };
+        mem.highlight(off) || changed
+    }
+
+    // Called by the C++ editor for every byte it reads
+    fn read_cell(&mut self, mem: &mut MemData<'a, '_, T, C>, off: usize) -> u8 {
+        // The cell starting to be edited is read before its input is drawn
+        if off != self.raw.DataEditingAddr || !self.raw.DataEditingTakeFocus {
+            self.pop_flat_colors();
+        }
+        let read = self.state.layout.classify_read(off);
+        if self.state.layout.truncated && read != Read::Other && off != self.raw.DataEditingAddr {
+            return if read == Read::Ascii { b' ' } else { 0 };
+        }
+        // The cell starting to be edited is read to fill the input, which is written back as is on enter,
+        // so it gets the raw value rather than the displayed one
+        let d = if off == self.raw.DataEditingAddr && self.raw.DataEditingTakeFocus {
+            mem.read(off)
+        } else {
+            mem.display(off)
+        };
+        let missing = match (self.state.missing_fn, mem.user_data()) {
+            (Some(missing_fn), Some(user_data)) => missing_fn(user_data, off),
+            _ => false,
+        };
+        match read {
+            Read::Ascii if missing => b'?',
+            Read::Ascii => self.draw_ascii(mem.ui, off, d),
+            // Read after the C++ editor has drawn the cell's highlight
+            Read::Hex => {
+                if let Some(color) = mem.flag(off) {
+                    self.draw_flag(mem.ui, off, color);
+                }
+                // The last cell of a row is read after all highlights of the row were drawn,
+                // unless it's being edited, in which case markers were drawn when visiting it
+                if off % self.state.layout.cols == self.state.layout.cols - 1 || off + 1 == self.state.mem_size {
+                    self.draw_markers(mem.ui, off);
+                }
+                // The cell being edited is read to fill the input
+                let text = if missing {
+                    Some("??".to_string())
+                } else {
+                    // Plain hex has to be drawn here too while HexII is only on for the other cells
+                    let hex = || self.format_hex(d as usize, 2);
+                    let hex_ii_forced = self.state.layout.hex_ii_forced;
+                    self.state.value_display.text(d).or_else(|| if hex_ii_forced { Some(hex()) } else { None })
+                };
+                match text {
+                    Some(text) if off != self.raw.DataEditingAddr => {
+                        self.draw_value(mem.ui, off, d, &text);
+                        0
+                    }
+                    _ => d,
+                }
+            }
+            Read::Other => d,
+        }
+    }
+
+    fn draw_value(&self, ui: &Ui, off: usize, d: u8, text: &str) {
+        let sizes = &self.state.layout.sizes;
+        let mut pos = self.state.layout.cell_pos(off);
+        let color = if d == 0 && self.raw.OptGreyOutZeroes {
+            ui.style_color(StyleColor::TextDisabled)
+        } else {
+            ui.style_color(StyleColor::Text)
+        };
+        let width = ui.calc_text_size(&ImString::new(text), false, -1.0)[0];
+        let max_width = sizes.HexCellWidth - sizes.GlyphWidth * 0.5;
+        if width <= max_width {
+            ui.get_window_draw_list().add_text(pos, color, text);
+            return;
+        }
+        // The draw list wrapper has no way to set the font size
+        let font_size = ui.current_font_size() * max_width / width;
+        pos[1] += (sizes.LineHeight - font_size) / 2.0;
+        unsafe {
+            imgui::sys::ImDrawList_AddText_FontPtr(
+                imgui::sys::igGetWindowDrawList(),
+                imgui::sys::igGetFont(),
+                font_size,
+                imgui::sys::ImVec2::new(pos[0], pos[1]),
+                ImColor32::from(color).to_bits(),
+                text.as_ptr() as *const _,
+                text.as_ptr().add(text.len()) as *const _,
+                0.0,
+                ptr::null(),
+            );
+        }
+    }
+
+    fn draw_markers(&self, ui: &Ui, off: usize) {
+        let row = self.state.layout.row(off);
+        let (min, max) = self.state.layout.row_rect(off);
+        let draw_list = ui.get_window_draw_list();
+        let in_row = |addr: usize| addr < self.state.mem_size && self.state.layout.row(addr) == row;
+        for &(_, color) in self.state.markers.iter().filter(|&&(addr, _)| in_row(addr)) {
+            draw_list.add_line([min[0], max[1] - 1.0], [max[0], max[1] - 1.0], color).thickness(2.0).build();
+        }
+    }
+
+    fn draw_flag(&self, ui: &Ui, off: usize, color: ImColor32) {
+        let pos = self.state.layout.cell_pos(off);
+        let size = (self.state.layout.sizes.LineHeight * 0.35).floor();
+        let right = pos[0] + self.state.layout.sizes.HexCellWidth - self.state.layout.sizes.GlyphWidth * 0.5;
+        ui.get_window_draw_list()
+            .add_triangle([right - size, pos[1]], [right, pos[1]], [right, pos[1] + size], color)
+            .filled(true)
+            .build();
+    }
+
+    // Called by the C++ editor for every byte it writes
+    // source is None for writes not typed in by the user, e.g. deleting the selection
+    fn write_cell(&mut self, mem: &mut MemData<'a, '_, T, C>, off: usize, d: u8, source: Option<EditSource>) {
+        // Only typed edits are aligned, writes of other bytes like Delete's go through
+        if source.is_some() && off % self.state.edit_alignment != 0 {
+            return;
+        }
+        match mem.write(off, d) {
+            Ok(()) => {
+                self.state.last_write = Some(off);
+                self.state.last_edit_source = source;
+                self.state.last_write_error = None;
+            }
+            Err(error) => {
+                if let Some(on_write_error) = self.state.on_write_error.as_mut() {
+                    on_write_error(off, &error);
+                }
+                self.state.last_write_error = Some(error);
+            }
+        }
+    }
+
+    // Scrolls on the next draw if the last write isn't fully visible. Only the last of a burst of writes is followed.
+    fn follow_last_write(&mut self) {
+        let off = match self.state.last_write.take() {
+            Some(off) if self.state.follow_writes && off < self.state.mem_size => off,
+            _ => return,
+        };
+        let layout = &self.state.layout;
+        let line_height = layout.sizes.LineHeight;
+        let y = layout.row(off) as f32 * line_height;
+        if y < layout.scroll_y || y + line_height > layout.scroll_y + layout.window_size[1] {
+            // Same position as a goto, but without starting to edit the byte
+            self.state.pending_scroll = Some(y.min(layout.scroll_max_y));
+        }
+    }
+
+    // Custom ASCII column rendering. Returns the byte for the C++ editor to draw,
+    // which is a space if the character was already drawn here.
+    fn draw_ascii(&self, ui: &Ui, off: usize, d: u8) -> u8 {
+        let mut buf = [0; 4];
+        let (glyph, color) = match d {
+            b'\t' if self.state.ascii_show_control => ("\u{21E5}", StyleColor::Text),
+            b'\n' if self.state.ascii_show_control => ("\u{21B5}", StyleColor::Text),
+            b'\r' if self.state.ascii_show_control => ("\u{2190}", StyleColor::Text),
+            // Shifted columns are drawn here entirely, the same way as the C++ editor
+            _ if self.state.layout.ascii_shift > 0.0 => match d {
+                32..=127 => (char::from(d).encode_utf8(&mut buf) as &str, StyleColor::Text),
+                _ => (".", StyleColor::TextDisabled),
+            },
+            _ => return d,
+        };
+        let pos = self.state.layout.ascii_pos(off);
+        let draw_list = ui.get_window_draw_list();
+        if self.state.layout.ascii_shift > 0.0 && off == self.raw.DataEditingAddr {
+            let max = [pos[0] + self.state.layout.sizes.GlyphWidth, pos[1] + self.state.layout.sizes.LineHeight];
+            draw_list.add_rect(pos, max, ui.style_color(StyleColor::FrameBg)).filled(true).build();
+            draw_list.add_rect(pos, max, ui.style_color(StyleColor::TextSelectedBg)).filled(true).build();
+        }
+        draw_list.add_text(pos, ui.style_color(color), glyph);
+        b' '
+    }
+
+    fn draw_divider(&self, ui: &Ui, off: usize) {
+        let sizes = &self.state.layout.sizes;
+        let x = self.state.layout.origin[0] + (sizes.PosHexEnd + sizes.PosAsciiStart) / 2.0;
+        let y = self.state.layout.cell_pos(off)[1];
+        let color = ui.style_color(StyleColor::Separator);
+        ui.get_window_draw_list().add_line([x, y], [x, y + sizes.LineHeight], color).build();
+    }
+
+    // Same area as the C++ editor's highlight
+    fn fill_cell(&self, ui: &Ui, off: usize, color: ImColor32) {
+        let pos = self.state.layout.cell_pos(off);
+        let max = [pos[0] + self.state.layout.sizes.GlyphWidth * 2.0, pos[1] + self.state.layout.sizes.LineHeight];
+        ui.get_window_draw_list().add_rect(pos, max, color).filled(true).build();
+    }
+
+    fn draw_crosshair(&self, ui: &Ui, off: usize, color: ImColor32) {
+        let layout = &self.state.layout;
+        let hovered = match layout.hovered_addr {
+            Some(hovered) => hovered,
+            None => return,
+        };
+        let draw_list = ui.get_window_draw_list();
+        if off % layout.cols == 0 && layout.row(off) == layout.row(hovered) {
+            let (min, max) = layout.row_rect(off);
+            draw_list.add_rect(min, max, color).filled(true).build();
+        }
+        if off % layout.cols == hovered % layout.cols && layout.row(off) != layout.row(hovered) {
+            let pos = layout.cell_pos(off);
+            let max = [pos[0] + layout.sizes.HexCellWidth, pos[1] + layout.sizes.LineHeight];
+            draw_list.add_rect(pos, max, color).filled(true).build();
+        }
+    }
+
+    // Address column text replacing the C++ editor's, if any
+    fn address_label(&self, off: usize) -> Option<String> {
+        let mut secondary_addr_fn = self.state.secondary_addr_fn.borrow_mut();
+        let mut label = match self.state.relative_to_bookmark {
+            Some(index) => self.bookmark_relative_addr(index, off),
+            None if secondary_addr_fn.is_some() || self.state.addr_prefix != AddrPrefix::None => {
+                self.state.addr_prefix.apply(self.format_hex(self.state.base_addr + off, self.state.layout.addr_digits))
+            }
+            None => return None,
+        };
+        if let Some(f) = secondary_addr_fn.as_mut() {
+            let digits = self.state.secondary_digits.get().max(self.state.layout.addr_digits);
+            let secondary = self.format_hex(f(off), digits);
+            // Wider addresses make room for themselves from the next frame on
+            self.state.secondary_digits.set(digits.max(secondary.len()));
+            label.push(' ');
+            label.push_str(&self.state.addr_prefix.apply(secondary));
+        }
+        Some(label)
+    }
+
+    // e.g. "entry+0x10"
+    fn bookmark_relative_addr(&self, index: usize, off: usize) -> String {
+        let bookmark = &self.state.bookmarks[index];
+        let (sign, distance) = if off >= bookmark.offset {
+            ('+', off - bookmark.offset)
+        } else {
+            ('-', bookmark.offset - off)
+        };
+        if self.raw.OptUpperCaseHex {
+            format!("{}{}0x{:X}", bookmark.name, sign, distance)
+        } else {
+            format!("{}{}0x{:x}", bookmark.name, sign, distance)
+        }
+    }
+
+    // Longest address label, in characters
+    fn address_label_len(&self, addr_digits: usize) -> Option<usize> {
+        let has_secondary = self.state.secondary_addr_fn.borrow().is_some();
+        let primary = match self.state.relative_to_bookmark {
+            Some(index) => {
+                let bookmark = &self.state.bookmarks[index];
+                let distance = bookmark.offset.max(self.state.mem_size.saturating_sub(bookmark.offset));
+                let digits = format!("{:x}", distance).len();
+                bookmark.name.chars().count() + "+0x".len() + digits
+            }
+            None if has_secondary || self.state.addr_prefix != AddrPrefix::None => {
+                self.state.addr_prefix.len() + addr_digits
+            }
+            None => return None,
+        };
+        if has_secondary {
+            let secondary = self.state.addr_prefix.len() + self.state.secondary_digits.get().max(addr_digits);
+            Some(primary + " ".len() + secondary)
+        } else {
+            Some(primary)
+        }
+    }
+
+    // The C++ editor has already drawn its address, so cover it before drawing the label
+    fn draw_address(&self, ui: &Ui, off: usize) {
+        let label = match self.address_label(off) {
+            Some(label) => label + ":",
+            None => return,
+        };
+        let layout = &self.state.layout;
+        let y = layout.cell_pos(off)[1];
+        let min = [layout.origin[0], y];
+        let max = [layout.origin[0] + layout.sizes.PosHexStart - layout.sizes.GlyphWidth, y + layout.sizes.LineHeight];
+        let draw_list = ui.get_window_draw_list();
+        draw_list.add_rect(min, max, self.content_background(ui)).filled(true).build();
+        draw_list.add_text(min, ui.style_color(StyleColor::Text), &label);
+    }
+
+    // Color behind the hex and ASCII columns
+    fn content_background(&self, ui: &Ui) -> ImColor32 {
+        if let Some(color) = self.state.background_color {
+            return color;
+        }
+        let child_bg = ui.style_color(StyleColor::ChildBg);
+        if child_bg[3] > 0.0 {
+            child_bg.into()
+        } else {
+            ui.style_color(StyleColor::WindowBg).into()
+        }
+    }
+
+    fn draw_sections(&self, ui: &Ui, off: usize) {
+        let layout = &self.state.layout;
+        let mut labels = self.state.sections.range(off..off + layout.cols).map(|(_, label)| label).peekable();
+        if labels.peek().is_none() {
+            return;
+        }
+        let (min, max) = layout.row_rect(off);
+        let color = ui.style_color(StyleColor::Separator);
+        let text_color = ui.style_color(StyleColor::Text);
+        let draw_list = ui.get_window_draw_list();
+        draw_list.add_line([layout.origin[0], min[1]], [max[0], min[1]], color).build();
+        let mut x = self.trailing_x();
+        for label in labels {
+            draw_list.add_text([x, min[1]], text_color, label);
+            x += ui.calc_text_size(&ImString::new(label.as_str()), false, -1.0)[0] + layout.sizes.GlyphWidth;
+        }
+    }
+
+    // Widest row of section labels as drawn by draw_sections, which draws them starting at trailing_x
+    fn sections_width(&self, ui: &Ui) -> f32 {
+        let mut rows = BTreeMap::new();
+        for (&off, label) in &self.state.sections {
+            let text_width = ui.calc_text_size(&ImString::new(label.as_str()), false, -1.0)[0];
+            let width = text_width + self.state.layout.sizes.GlyphWidth;
+            *rows.entry(off / self.state.layout.cols).or_insert(0.0) += width;
+        }
+        rows.values().fold(0.0, |max: f32, &width| max.max(width))
+    }
+
+    // Start of the space to the right of the ASCII column, any sparkline and the extra columns
+    #[inline]
+    fn trailing_x(&self) -> f32 {
+        self.state.layout.origin[0] + self.trailing_offset()
+    }
+
+    // trailing_x from the start of the row
+    fn trailing_offset(&self) -> f32 {
+        let sizes = &self.state.layout.sizes;
+        let mut x = sizes.PosAsciiEnd + sizes.GlyphWidth;
+        if self.state.row_sparkline {
+            x += sizes.GlyphWidth * (SPARKLINE_GLYPHS + 1.0);
+        }
+        x + self.extra_columns_width()
+    }
+
+    fn extra_columns_width(&self) -> f32 {
+        let cols = self.state.layout.cols as f32;
+        let glyphs: f32 = self.state.extra_columns.iter().map(|col| col.glyphs() * cols + 1.0).sum();
+        glyphs * self.state.layout.sizes.GlyphWidth
+    }
+
+    fn draw_extra_columns(&self, mem: &mut MemData<'a, '_, T, C>, off: usize) {
+        let ui = mem.ui;
+        let layout = &self.state.layout;
+        let start = off - off % layout.cols;
+        let end = (start + layout.cols).min(layout.mem_size);
+        let y = layout.cell_pos(start)[1];
+        let bytes: Vec<u8> = (start..end).map(|off| mem.display(off)).collect();
+
+        let draw_list = ui.get_window_draw_list();
+        let color = ui.style_color(StyleColor::Text);
+        let mut x = self.trailing_x() - self.extra_columns_width();
+        for col in &self.state.extra_columns {
+            for (i, &d) in bytes.iter().enumerate() {
+                let pos = [x + i as f32 * col.glyphs() * layout.sizes.GlyphWidth, y];
+                draw_list.add_text(pos, color, &col.text(d, self.raw.OptUpperCaseHex));
+            }
+            x += (col.glyphs() * layout.cols as f32 + 1.0) * layout.sizes.GlyphWidth;
+        }
+    }
+
+    fn draw_row_sparkline(&self, mem: &mut MemData<'a, '_, T, C>, off: usize) {
+        let ui = mem.ui;
+        let layout = &self.state.layout;
+        let start = off - off % layout.cols;
+        let end = (start + layout.cols).min(layout.mem_size);
+        let x = layout.origin[0] + layout.sizes.PosAsciiEnd + layout.sizes.GlyphWidth;
+        let y = layout.cell_pos(start)[1] + 1.0;
+        let width = layout.sizes.GlyphWidth * SPARKLINE_GLYPHS;
+        let height = layout.sizes.LineHeight - 2.0;
+        let step = if end - start > 1 { width / (end - start - 1) as f32 } else { 0.0 };
+
+        let color = ui.style_color(StyleColor::PlotLines);
+        let draw_list = ui.get_window_draw_list();
+        let mut prev = None;
+        for (i, addr) in (start..end).enumerate() {
+            let value = mem.display(addr) as f32 / 255.0;
+            let point = [x + step * i as f32, y + height * (1.0 - value)];
+            if let Some(prev) = prev {
+                draw_list.add_line(prev, point, color).build();
+            }
+            prev = Some(point);
+        }
+    }
+
+    fn handle_paging_keys(&mut self, ui: &Ui) {
+        if !self.state.paging_keys || !self.state.layout.focused || self.is_editing() {
+            return;
+        }
+        let rows = self.state.page_rows.map_or(self.visible_row_count(), |rows| rows as usize);
+        let page = rows.max(1) as f32 * self.state.layout.sizes.LineHeight;
+        let scroll_y = self.state.layout.scroll_y;
+        let scroll_max_y = self.state.layout.scroll_max_y;
+        self.state.pending_scroll = if ui.is_key_pressed(Key::PageUp) {
+            Some((scroll_y - page).max(0.0))
+        } else if ui.is_key_pressed(Key::PageDown) {
+            Some((scroll_y + page).min(scroll_max_y))
+        } else if ui.is_key_pressed(Key::Home) {
+            Some(0.0)
+        } else if ui.is_key_pressed(Key::End) {
+            Some(scroll_max_y)
+        } else {
+            self.state.pending_scroll
+        };
+    }
+
+    fn assert_no_handlers(&mut self) {
+        // TODO: Support highlight fn
+        assert!(
+            self.read_fn.get_mut().is_none()
+                && self.write_fn.is_none()
+                && self.write_result_fn.is_none()
+                && self.highlight_fn.is_none()
+                && self.display_fn.is_none()
+                && self.flag_fn.is_none()
+                && self.state.diff_baseline.is_none(),
+            "Handler functions not supported when drawing bytes directly. Use draw instead"
+        );
+    }
+
+    // Draw straight from a byte buffer of mem_size bytes. Handlers are not used.
+    fn draw_bytes(&mut self, ui: &Ui, bytes: *mut u8) {
+        let mut data = MemData {
+            ctx: self.ctx.as_ptr(),
+            editor: self,
+            ui,
+            read_fn: None,
+            write_fn: None,
+            write_result_fn: None,
+            highlight_fn: None,
+            display_fn: None,
+            flag_fn: None,
+            source: Source::Bytes(bytes),
+            reads: 0,
+        };
+        self.draw_frame(&mut data);
+        self.state.last_reads = data.reads;
+    }
+
+    pub unsafe fn draw_raw(&mut self, mem_data: *mut c_void) {
+        if let Some(title) = self.state.window_name {
+            sys::Editor_DrawWindow(
+                &mut self.raw,
+                title.as_ptr(),
+                mem_data,
+                self.state.mem_size,
+                self.state.base_addr,
+            );
+        } else {
+            sys::Editor_DrawContents(
+                &mut self.raw,
+                mem_data,
+                self.state.mem_size,
+                self.state.base_addr,
+            );
+        }
+    }
+}
+
+impl<'a> MemoryEditor<'a, ChannelSource> {
+    // Editor reading and writing through a ChannelSource. Bytes that haven't arrived yet are drawn as ??.
+    pub fn from_channel_source(mem_size: usize) -> Self {
+        let mut editor = MemoryEditor::new()
+            .mem_size(mem_size)
+            .read_fn(|source: &ChannelSource, off| source.read(off).unwrap_or(0))
+            .write_fn(|source: &mut ChannelSource, off, d| source.write(off, d));
+        editor.state.missing_fn = Some(|source, off| !source.is_cached(off));
+        editor
+    }
+}
+
+impl<'a> MemoryEditor<'a, &[u8]> {
+    pub fn draw_vec(&mut self, ui: &Ui, data: &[u8]) {
+        assert!(self.raw.ReadOnly, "Data must be a mutable slice if editor is not read only");
+        self.assert_no_handlers();
+        self.state.mem_size = data.len();
+        self.draw_bytes(ui, data.as_ptr() as *mut u8);
+    }
+}
+
+
+// Convenience implementations
+impl<'a> MemoryEditor<'a, &mut [u8]> {
+    pub fn draw_vec(&mut self, ui: &Ui, data: &mut [u8]) {
+        self.assert_no_handlers();
+        self.state.mem_size = data.len();
+        self.draw_bytes(ui, data.as_mut_ptr());
+    }
+}
+
+impl<'a, const N: usize> MemoryEditor<'a, &[u8; N]> {
+    pub fn draw_array(&mut self, ui: &Ui, data: &[u8; N]) {
+        assert!(self.raw.ReadOnly, "Data must be a mutable array if editor is not read only");
+        self.assert_no_handlers();
+        self.state.mem_size = N;
+        self.draw_bytes(ui, data.as_ptr() as *mut u8);
+    }
+}
+
+impl<'a, const N: usize> MemoryEditor<'a, &mut [u8; N]> {
+    pub fn draw_array(&mut self, ui: &Ui, data: &mut [u8; N]) {
+        self.assert_no_handlers();
+        self.state.mem_size = N;
+        self.draw_bytes(ui, data.as_mut_ptr());
+    }
+}
+
+fn byteview_color(d: u8) -> ImColor32 {
+    match d {
+        0x00 => ImColor32::from_rgb(0, 0, 0),
+        0xFF => ImColor32::from_rgb(255, 255, 255),
+        0x20..=0x7E => ImColor32::from_rgb(55, 126, 184),
+        0x01..=0x1F | 0x7F => ImColor32::from_rgb(77, 175, 74),
+        _ => ImColor32::from_rgb(228, 26, 28),
+    }
 }
 
-unsafe extern "C" fn write_wrapper<'a, T>(data: *mut u8, off: usize, d: u8) {
-    let (_, write_fn, _, user_data) = &mut *(data as *mut MemData<T>);
-    write_fn.as_mut().unwrap()(user_data, off, d);
+// Called by the C++ editor while drawing, with the MemData draw_sys_contents pointed at the editor
+unsafe extern "C" fn read_wrapper<'a, T, C>(data: *const u8, off: usize) -> u8 {
+    let mem = &mut *(data as *mut MemData<T, C>);
+    let editor = &mut *mem.editor;
+    editor.read_cell(mem, off)
 }
 
-unsafe extern "C" fn highlight_wrapper<'a, T>(data: *const u8, off: usize) -> bool {
-    let (_, _, highlight_fn, user_data) = &mut *(data as *mut MemData<T>);
-    highlight_fn.as_mut().unwrap()(user_data, off)
+unsafe extern "C" fn write_wrapper<'a, T, C>(data: *mut u8, off: usize, d: u8) {
+    let mem = &mut *(data as *mut MemData<T, C>);
+    let editor = &mut *mem.editor;
+    let source = editor.state.edit_source;
+    editor.write_cell(mem, off, d, Some(source));
+}
+
+unsafe extern "C" fn highlight_wrapper<'a, T, C>(data: *const u8, off: usize) -> bool {
+    let mem = &mut *(data as *mut MemData<T, C>);
+    let editor = &mut *mem.editor;
+    editor.visit_cell(mem, off)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cols_below_one_are_clamped() {
+        let editor = MemoryEditor::<&[u8]>::new().cols(0);
+        assert_eq!(editor.raw.Cols, 1);
+        assert!(editor.cols_clamped());
+
+        let editor = MemoryEditor::<&[u8]>::new().cols(-4).cols(8);
+        assert_eq!(editor.raw.Cols, 8);
+        assert!(!editor.cols_clamped());
+
+        let mut editor = MemoryEditor::<&[u8]>::new();
+        editor.raw.Cols = 0;
+        editor.clamp_cols();
+        assert_eq!(editor.raw.Cols, 1);
+        assert!(editor.cols_clamped());
+    }
+
+    #[test]
+    fn addresses_relative_to_a_bookmark() {
+        let mut editor = MemoryEditor::<Vec<u8>>::new().mem_size(0x100).upper_case_hex(true);
+        assert_eq!(editor.address_label(0x10), None);
+        assert_eq!(editor.address_label_len(2), None);
+
+        let entry = editor.add_bookmark(0x40, "entry".to_string());
+        editor.relative_to_bookmark(Some(entry));
+        assert_eq!(editor.address_label(0x4A).as_deref(), Some("entry+0xA"));
+        assert_eq!(editor.address_label(0x3F).as_deref(), Some("entry-0x1"));
+        assert_eq!(editor.history_label(0x41), "entry+0x1");
+        editor.add_bookmark(0x50, "data".to_string());
+        assert_eq!(editor.history_label(0x50), "entry+0x10 (data)");
+        // The farthest address is the end of the memory, 0xC0 past the bookmark
+        assert_eq!(editor.address_label_len(2), Some("entry+0xC0".len()));
+    }
+
+    #[test]
+    fn removing_a_bookmark_shifts_the_relative_one() {
+        let mut editor = MemoryEditor::<Vec<u8>>::new().mem_size(0x100);
+        let first = editor.add_bookmark(0x10, "a".to_string());
+        editor.add_bookmark(0x20, "b".to_string());
+        let last = editor.add_bookmark(0x30, "c".to_string());
+        editor.relative_to_bookmark(Some(last));
+
+        editor.remove_bookmark(first);
+        assert_eq!(editor.bookmarks().collect::<Vec<_>>(), [(0x20, "b"), (0x30, "c")]);
+        assert_eq!(editor.address_label(0x31).as_deref(), Some("c+0x1"));
+
+        editor.remove_bookmark(1);
+        assert_eq!(editor.bookmarks().collect::<Vec<_>>(), [(0x20, "b")]);
+        assert_eq!(editor.address_label(0x31), None);
+    }
+
+    #[test]
+    fn addr_prefixes() {
+        let prefixes = [
+            (AddrPrefix::None, "1F"),
+            (AddrPrefix::ZeroX, "0x1F"),
+            (AddrPrefix::Dollar, "$1F"),
+            (AddrPrefix::HSuffix, "1Fh"),
+        ];
+        for &(prefix, text) in prefixes.iter() {
+            assert_eq!(prefix.apply("1F".to_string()), text);
+            assert_eq!(prefix.len(), text.len() - "1F".len());
+        }
+
+        let editor = MemoryEditor::<Vec<u8>>::new()
+            .mem_size(2)
+            .cols(2)
+            .mid_cols_count(0)
+            .show_ascii(false)
+            .addr_prefix(AddrPrefix::HSuffix)
+            .read_fn(|data, off| data[off]);
+        assert_eq!(editor.render_to_string(&vec![0x01, 0x02]), "0h: 01 02\n");
+    }
+
+    #[test]
+    fn column_widths_include_the_addr_prefix() {
+        let mut imgui = imgui::Context::create();
+        imgui.set_ini_filename(None);
+        imgui.io_mut().display_size = [800.0, 600.0];
+        imgui.fonts().build_rgba32_texture();
+        let mut widths = |prefix| {
+            let mut editor = MemoryEditor::<Vec<u8>>::new()
+                .mem_size(0x100)
+                .addr_prefix(prefix)
+                .hex_ascii_divider(false, 40.0)
+                .read_fn(|data, off| data[off]);
+            let ui = imgui.frame();
+            editor.draw(&ui, &mut vec![0; 0x100]);
+            let widths = (editor.cell_width(&ui), editor.addr_column_width(&ui), editor.ascii_column_offset(&ui));
+            let glyph = editor.state.layout.sizes.GlyphWidth;
+            ui.render();
+            (widths, glyph)
+        };
+        let ((cell, addr, ascii), glyph) = widths(AddrPrefix::None);
+        let ((prefixed_cell, prefixed_addr, prefixed_ascii), _) = widths(AddrPrefix::ZeroX);
+        assert!(glyph > 0.0);
+        assert_eq!(prefixed_cell, cell);
+        assert_eq!(prefixed_addr, addr + 2.0 * glyph);
+        assert_eq!(prefixed_ascii, ascii + 2.0 * glyph);
+    }
+
+    #[test]
+    fn value_display_text() {
+        assert_eq!(ValueDisplay::Hex.text(0xAB), None);
+        assert_eq!(ValueDisplay::Dec.text(0xAB).as_deref(), Some("171"));
+        assert_eq!(ValueDisplay::Percent.text(0).as_deref(), Some("0%"));
+        assert_eq!(ValueDisplay::Percent.text(128).as_deref(), Some("50%"));
+        assert_eq!(ValueDisplay::Percent.text(255).as_deref(), Some("100%"));
+        assert_eq!(ValueDisplay::Normalized.text(51).as_deref(), Some("0.20"));
+        assert_eq!(ValueDisplay::Normalized.text(255).as_deref(), Some("1.00"));
+    }
+
+    #[test]
+    fn byte_column_text() {
+        assert_eq!(ByteColumn::Hex.text(0xAB, true), "AB");
+        assert_eq!(ByteColumn::Hex.text(0xAB, false), "ab");
+        assert_eq!(ByteColumn::Dec.text(7, true), "  7");
+        assert_eq!(ByteColumn::Oct.text(8, true), "010");
+        assert_eq!(ByteColumn::Char.text(b'A', true), "A");
+        assert_eq!(ByteColumn::Char.text(0x7F, true), ".");
+        // Every column but Char is as wide as its widest value plus a space
+        assert_eq!(ByteColumn::Dec.text(255, true).len() as f32 + 1.0, ByteColumn::Dec.glyphs());
+        assert_eq!(ByteColumn::Oct.text(255, true).len() as f32 + 1.0, ByteColumn::Oct.glyphs());
+    }
+
+    #[test]
+    fn content_hash_is_fnv_1a_of_the_scan_range() {
+        let editor = MemoryEditor::<Vec<u8>>::new().mem_size(0).read_fn(|data, off| data[off]);
+        assert_eq!(editor.content_hash(&vec![]), 0xcbf2_9ce4_8422_2325);
+
+        let editor = MemoryEditor::<Vec<u8>>::new().mem_size(1).read_fn(|data, off| data[off]);
+        assert_eq!(editor.content_hash(&b"a".to_vec()), 0xaf63_dc4c_8601_ec8c);
+
+        // Bytes past the scan limit don't count
+        let editor = MemoryEditor::<Vec<u8>>::new().mem_size(3).scan_limit(Some(1)).read_fn(|data, off| data[off]);
+        assert_eq!(editor.content_hash(&b"abc".to_vec()), editor.content_hash(&b"axy".to_vec()));
+        assert_ne!(editor.content_hash(&b"abc".to_vec()), editor.content_hash(&b"bbc".to_vec()));
+    }
+
+    #[test]
+    fn magic_badges_match_only_inside_the_memory() {
+        let data = b"\x7FELF";
+        let badge = |offset, magic: &[u8]| MagicBadge { offset, magic: magic.to_vec(), label: String::new() };
+        assert!(badge(0, b"\x7FELF").matches(4, |off| data[off]));
+        assert!(badge(1, b"ELF").matches(4, |off| data[off]));
+        assert!(!badge(0, b"\x7FELG").matches(4, |off| data[off]));
+        assert!(!badge(2, b"LF\0").matches(4, |off| data[off]));
+        assert!(!badge(usize::MAX, b"EL").matches(usize::MAX, |_| unreachable!()));
+        assert!(!badge(usize::MAX - 1, b"EL").matches(4, |_| unreachable!()));
+    }
+
+    fn dump(hex_ii: bool, grey_out_zeroes: bool) -> String {
+        let mut editor = MemoryEditor::<Vec<u8>>::new()
+            .mem_size(6)
+            .cols(4)
+            .read_fn(|data, off| data[off]);
+        editor.raw.OptMidColsCount = 0;
+        editor.raw.OptAddrDigitsCount = 0;
+        editor.raw.OptShowAscii = true;
+        editor.raw.OptUpperCaseHex = true;
+        editor.raw.OptShowHexII = hex_ii;
+        editor.raw.OptGreyOutZeroes = grey_out_zeroes;
+        editor.render_to_string(&vec![0x00, 0x41, 0xFF, 0x10, 0x7E, 0x20])
+    }
+
+    #[test]
+    fn render_to_string_hex() {
+        let expected = format!("0: 00 41 FF 10  .A..\n4: 7E 20{}~\n", " ".repeat(8));
+        assert_eq!(dump(false, true), expected);
+        assert_eq!(dump(false, false), expected);
+    }
+
+    #[test]
+    fn render_to_string_hex_ii() {
+        let tail = format!("4: .~ .{}~\n", " ".repeat(9));
+        assert_eq!(dump(true, true), format!("0:    .A ## 10  .A..\n{}", tail));
+        assert_eq!(dump(true, false), format!("0:    .A FF 10  .A..\n{}", tail));
+    }
+
+    #[test]
+    fn render_to_string_stops_at_scan_limit() {
+        let mut editor = MemoryEditor::<Vec<u8>>::new()
+            .mem_size(6)
+            .cols(4)
+            .scan_limit(Some(2))
+            .read_fn(|data, off| data[off]);
+        editor.raw.OptMidColsCount = 0;
+        editor.raw.OptShowAscii = false;
+        editor.raw.OptShowHexII = false;
+        assert_eq!(editor.render_to_string(&vec![0xAB, 0xCD, 0xEF, 0, 0, 0]), "0: AB CD\n");
+    }
+
+    #[test]
+    fn diff_count_uses_diff_with_eq() {
+        let baseline = vec![10, 20, 30, 40];
+        let data = vec![10, 21, 35, 40];
+        let editor = MemoryEditor::<Vec<u8>>::new().mem_size(4).read_fn(|data, off| data[off]);
+        assert_eq!(editor.diff_count(&data, &baseline), 2);
+
+        let editor = MemoryEditor::<Vec<u8>>::new()
+            .mem_size(4)
+            .read_fn(|data, off| data[off])
+            .diff_with(&baseline, |_, a, b| (a as i32 - b as i32).abs() <= 1);
+        assert_eq!(editor.diff_count(&data, &baseline), 1);
+    }
+
+    #[test]
+    fn with_context_keeps_settings_and_handlers() {
+        let editor = MemoryEditor::<Vec<u8>>::new()
+            .mem_size(2)
+            .cols(2)
+            .mid_cols_count(0)
+            .show_ascii(true)
+            .upper_case_hex(true)
+            .read_fn(|data, off| data[off])
+            .with_context(0x10u8);
+        assert_eq!(editor.render_to_string(&vec![0x01, 0x02]), "0: 01 02  ..\n");
+
+        let mut editor = editor.read_fn_ctx(|data, offset, off| data[off] + *offset);
+        *editor.context_mut() = 0x20;
+        assert_eq!(editor.render_to_string(&vec![0x01, 0x02]), "0: 21 22  !\"\n");
+    }
+
+    #[test]
+    fn gotos_out_of_range_follow_the_policy() {
+        let mut editor = MemoryEditor::<Vec<u8>>::new().mem_size(0x10);
+        editor.goto_addr(0x20);
+        assert_eq!(editor.raw.GotoAddr, 0xF);
+        assert_eq!(editor.state.history, [0xF]);
+
+        let mut editor = MemoryEditor::<Vec<u8>>::new().mem_size(0).goto_out_of_range(OutOfRangePolicy::Clamp);
+        editor.goto_addr(0x20);
+        assert_eq!(editor.raw.GotoAddr, usize::MAX);
+
+        let mut editor = MemoryEditor::<Vec<u8>>::new().mem_size(0x10).goto_out_of_range(OutOfRangePolicy::Ignore);
+        editor.goto_addr(0x20);
+        assert_eq!(editor.raw.GotoAddr, usize::MAX);
+        assert_eq!(editor.last_goto_error(), None);
+        assert!(editor.state.history.is_empty());
+
+        let mut editor = MemoryEditor::<Vec<u8>>::new().mem_size(0x10).goto_out_of_range(OutOfRangePolicy::Error);
+        editor.goto_addr(0x20);
+        assert_eq!(editor.raw.GotoAddr, usize::MAX);
+        assert_eq!(editor.last_goto_error(), Some(0x20));
+        editor.goto_addr(0x8);
+        assert_eq!(editor.raw.GotoAddr, 0x8);
+        assert_eq!(editor.last_goto_error(), None);
+    }
+
+    #[test]
+    fn frame_budgets_are_clamped() {
+        let editor = MemoryEditor::<Vec<u8>>::new().frame_budget(500.0);
+        assert_eq!(editor.state.frame_budget, Some(Duration::from_millis(500)));
+        let editor = MemoryEditor::<Vec<u8>>::new().frame_budget(f32::MAX);
+        assert_eq!(editor.state.frame_budget, Some(Duration::from_secs_f32(u32::MAX as f32)));
+    }
+
+    #[test]
+    #[should_panic(expected = "Frame budget must be")]
+    fn negative_frame_budgets_panic() {
+        MemoryEditor::<Vec<u8>>::new().frame_budget(-1.0);
+    }
+
+    #[test]
+    fn clear_all_keeps_the_context() {
+        let mut editor = MemoryEditor::<Vec<u8>>::new()
+            .mem_size(2)
+            .with_context(0x10u8)
+            .read_fn_ctx(|data, offset, off| data[off] + *offset);
+        editor.add_bookmark(1, "a".to_string());
+        editor.clear_all();
+        assert_eq!(editor.bookmarks().count(), 0);
+        assert!(editor.read_fn.get_mut().is_none());
+        assert_eq!(*editor.context_mut(), 0x10);
+    }
 }
@@ -0,0 +1,180 @@
+// Data types the preview can interpret the selected bytes as. Values match ImGuiDataType.
+#[repr(i32)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum PreviewDataType {
+    Int8 = 0,
+    Uint8 = 1,
+    Int16 = 2,
+    Uint16 = 3,
+    Int32 = 4,
+    Uint32 = 5,
+    Int64 = 6,
+    Uint64 = 7,
+    Float = 8,
+    Double = 9,
+}
+
+impl PreviewDataType {
+    pub const ALL: [PreviewDataType; 10] = [
+        PreviewDataType::Int8,
+        PreviewDataType::Uint8,
+        PreviewDataType::Int16,
+        PreviewDataType::Uint16,
+        PreviewDataType::Int32,
+        PreviewDataType::Uint32,
+        PreviewDataType::Int64,
+        PreviewDataType::Uint64,
+        PreviewDataType::Float,
+        PreviewDataType::Double,
+    ];
+
+    // Size in bytes
+    pub fn size(self) -> usize {
+        match self {
+            PreviewDataType::Int8 | PreviewDataType::Uint8 => 1,
+            PreviewDataType::Int16 | PreviewDataType::Uint16 => 2,
+            PreviewDataType::Int32 | PreviewDataType::Uint32 | PreviewDataType::Float => 4,
+            PreviewDataType::Int64 | PreviewDataType::Uint64 | PreviewDataType::Double => 8,
+        }
+    }
+
+    // Same names as the C++ editor's preview combo
+    pub fn name(self) -> &'static str {
+        match self {
+            PreviewDataType::Int8 => "Int8",
+            PreviewDataType::Uint8 => "Uint8",
+            PreviewDataType::Int16 => "Int16",
+            PreviewDataType::Uint16 => "Uint16",
+            PreviewDataType::Int32 => "Int32",
+            PreviewDataType::Uint32 => "Uint32",
+            PreviewDataType::Int64 => "Int64",
+            PreviewDataType::Uint64 => "Uint64",
+            PreviewDataType::Float => "Float",
+            PreviewDataType::Double => "Double",
+        }
+    }
+
+    pub fn is_integer(self) -> bool {
+        !matches!(self, PreviewDataType::Float | PreviewDataType::Double)
+    }
+
+    pub(crate) fn from_raw(raw: sys::ImGuiDataType) -> Option<PreviewDataType> {
+        PreviewDataType::ALL.iter().copied().find(|&ty| ty as sys::ImGuiDataType == raw)
+    }
+}
+
+// Bytes decoded for the preview
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct Preview {
+    pub ty: PreviewDataType,
+    pub bits: u64,
+}
+
+impl Preview {
+    // bytes are in memory order and zero padded by the caller if the memory ends early
+    pub fn decode(ty: PreviewDataType, bytes: &[u8], big_endian: bool) -> Preview {
+        let bytes = &bytes[..ty.size().min(bytes.len())];
+        let mut buf = [0; 8];
+        if big_endian {
+            buf.iter_mut().zip(bytes.iter().rev()).for_each(|(dst, &src)| *dst = src);
+        } else {
+            buf[..bytes.len()].copy_from_slice(bytes);
+        }
+        Preview { ty, bits: u64::from_le_bytes(buf) }
+    }
+
+    pub fn dec(&self) -> String {
+        let bits = self.bits;
+        match self.ty {
+            PreviewDataType::Int8 => (bits as u8 as i8).to_string(),
+            PreviewDataType::Uint8 => (bits as u8).to_string(),
+            PreviewDataType::Int16 => (bits as u16 as i16).to_string(),
+            PreviewDataType::Uint16 => (bits as u16).to_string(),
+            PreviewDataType::Int32 => (bits as u32 as i32).to_string(),
+            PreviewDataType::Uint32 => (bits as u32).to_string(),
+            PreviewDataType::Int64 => (bits as i64).to_string(),
+            PreviewDataType::Uint64 => bits.to_string(),
+            PreviewDataType::Float => f32::from_bits(bits as u32).to_string(),
+            PreviewDataType::Double => f64::from_bits(bits).to_string(),
+        }
+    }
+
+    // Both interpretations of an integer, e.g. "-1 / 255"
+    pub fn dec_signed_unsigned(&self) -> String {
+        if !self.ty.is_integer() {
+            return self.dec();
+        }
+        let shift = 64 - self.ty.size() * 8;
+        let signed = ((self.bits << shift) as i64) >> shift;
+        format!("{} / {}", signed, self.bits)
+    }
+
+    pub fn hex(&self, upper_case: bool) -> String {
+        let width = self.ty.size() * 2;
+        if upper_case {
+            format!("0x{:01$X}", self.bits, width)
+        } else {
+            format!("0x{:01$x}", self.bits, width)
+        }
+    }
+
+    // Most significant byte first
+    pub fn bin(&self) -> String {
+        (0..self.ty.size())
+            .rev()
+            .map(|i| format!("{:08b}", (self.bits >> (i * 8)) as u8))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_endianness() {
+        let bytes = [0x34, 0x12, 0xFF];
+        assert_eq!(Preview::decode(PreviewDataType::Uint16, &bytes, false).bits, 0x1234);
+        assert_eq!(Preview::decode(PreviewDataType::Uint16, &bytes, true).bits, 0x3412);
+        assert_eq!(Preview::decode(PreviewDataType::Uint8, &bytes, true).bits, 0x34);
+    }
+
+    #[test]
+    fn dec() {
+        assert_eq!(Preview::decode(PreviewDataType::Int8, &[0xFF], false).dec(), "-1");
+        assert_eq!(Preview::decode(PreviewDataType::Uint8, &[0xFF], false).dec(), "255");
+        assert_eq!(Preview::decode(PreviewDataType::Int32, &[0xFE, 0xFF, 0xFF, 0xFF], false).dec(), "-2");
+        assert_eq!(Preview::decode(PreviewDataType::Float, &[0x00, 0x00, 0x80, 0x3F], false).dec(), "1");
+        assert_eq!(Preview::decode(PreviewDataType::Float, &[0x3F, 0x80, 0x00, 0x00], true).dec(), "1");
+    }
+
+    #[test]
+    fn dec_signed_unsigned() {
+        assert_eq!(Preview::decode(PreviewDataType::Uint8, &[0xFF], false).dec_signed_unsigned(), "-1 / 255");
+        let min = Preview::decode(PreviewDataType::Int16, &[0x00, 0x80], false);
+        assert_eq!(min.dec_signed_unsigned(), "-32768 / 32768");
+        assert_eq!(Preview::decode(PreviewDataType::Int32, &[0x05, 0, 0, 0], false).dec_signed_unsigned(), "5 / 5");
+        let all_ones = Preview::decode(PreviewDataType::Uint64, &[0xFF; 8], false);
+        assert_eq!(all_ones.dec_signed_unsigned(), "-1 / 18446744073709551615");
+        // Floats only have the one interpretation
+        assert_eq!(Preview::decode(PreviewDataType::Double, &1.5f64.to_le_bytes(), false).dec_signed_unsigned(), "1.5");
+    }
+
+    #[test]
+    fn hex_and_bin() {
+        let preview = Preview::decode(PreviewDataType::Uint16, &[0xAB, 0x01], false);
+        assert_eq!(preview.hex(true), "0x01AB");
+        assert_eq!(preview.hex(false), "0x01ab");
+        assert_eq!(preview.bin(), "00000001 10101011");
+        assert_eq!(Preview::decode(PreviewDataType::Uint32, &[0x0F], false).hex(true), "0x0000000F");
+    }
+
+    #[test]
+    fn from_raw_matches_imgui_data_type() {
+        for &ty in PreviewDataType::ALL.iter() {
+            assert_eq!(PreviewDataType::from_raw(ty as sys::ImGuiDataType), Some(ty));
+        }
+        assert_eq!(PreviewDataType::from_raw(PreviewDataType::ALL.len() as sys::ImGuiDataType), None);
+    }
+}